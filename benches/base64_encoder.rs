@@ -0,0 +1,64 @@
+use core::convert::Infallible;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use embedded_io::{ErrorType, Write};
+use lil_jwt::{InsecureNoVerification, JsonField, JsonWebToken, JwtType};
+
+/// a writer that discards its input, like a socket that never blocks
+struct Sink;
+
+impl ErrorType for Sink {
+    type Error = Infallible;
+}
+
+impl Write for Sink {
+    fn write(&mut self, data: &[u8]) -> Result<usize, Self::Error> {
+        Ok(criterion::black_box(data).len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+const CLAIM_NAMES: [&str; 32] = [
+    "c00", "c01", "c02", "c03", "c04", "c05", "c06", "c07", "c08", "c09", "c10", "c11", "c12", "c13", "c14", "c15",
+    "c16", "c17", "c18", "c19", "c20", "c21", "c22", "c23", "c24", "c25", "c26", "c27", "c28", "c29", "c30", "c31",
+];
+
+fn encode_claims(c: &mut Criterion) {
+    let mut group = c.benchmark_group("base64_encode_claims");
+    for num_claims in [4, 32] {
+        let claims: Vec<JsonField> = CLAIM_NAMES[..num_claims].iter().map(|name| JsonField::new_string(name, "a claim value that is long enough to span a few base64 blocks")).collect();
+        let token = JsonWebToken::from_claims(&claims);
+        let token_len = token.serialized_len(JwtType::Unsecured(InsecureNoVerification));
+        group.throughput(Throughput::Bytes(token_len as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(num_claims), &token, |b, token| {
+            b.iter(|| token.serialize(Sink, JwtType::Unsecured(InsecureNoVerification), &[]).unwrap())
+        });
+    }
+    group.finish();
+}
+
+/// every write to /dev/null is a system call, like an unbuffered socket
+#[cfg(unix)]
+fn encode_claims_unbuffered(c: &mut Criterion) {
+    let mut dev_null = embedded_io_adapters::std::FromStd::new(std::fs::File::create("/dev/null").unwrap());
+    let mut group = c.benchmark_group("base64_encode_claims_unbuffered");
+    for num_claims in [4, 32] {
+        let claims: Vec<JsonField> = CLAIM_NAMES[..num_claims].iter().map(|name| JsonField::new_string(name, "a claim value that is long enough to span a few base64 blocks")).collect();
+        let token = JsonWebToken::from_claims(&claims);
+        let token_len = token.serialized_len(JwtType::Unsecured(InsecureNoVerification));
+        group.throughput(Throughput::Bytes(token_len as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(num_claims), &token, |b, token| {
+            b.iter(|| token.serialize(&mut dev_null, JwtType::Unsecured(InsecureNoVerification), &[]).unwrap())
+        });
+    }
+    group.finish();
+}
+
+#[cfg(unix)]
+criterion_group!(benches, encode_claims, encode_claims_unbuffered);
+#[cfg(not(unix))]
+criterion_group!(benches, encode_claims);
+criterion_main!(benches);
@@ -0,0 +1,51 @@
+//! claim sets & tokens shared by the benchmarks
+
+use std::collections::BTreeMap;
+
+use lil_jwt::{InsecureNoVerification, JsonField, JsonWebToken, JwtType, SignatureAlgorithm};
+
+pub const SECRET: &[u8] = b"a-string-secret-at-least-256-bits-long";
+
+/// claim set sizes from empty to several KB of JSON
+pub const CLAIM_COUNTS: [usize; 5] = [0, 4, 16, 64, 128];
+
+/// every claim has the same value, which makes each claim ~60 bytes of JSON
+const CLAIM_VALUE: &str = "a claim value that is long enough to span several blocks";
+
+pub const ALGORITHMS: [(&str, JwtType); 4] = [
+    ("none", JwtType::Unsecured(InsecureNoVerification)),
+    ("HS256", JwtType::Signed(SignatureAlgorithm::HS256)),
+    ("HS384", JwtType::Signed(SignatureAlgorithm::HS384)),
+    ("HS512", JwtType::Signed(SignatureAlgorithm::HS512)),
+];
+
+/// the names of a generated set of claims
+pub struct ClaimSet {
+    names: Vec<String>,
+}
+
+impl ClaimSet {
+    pub fn new(num_claims: usize) -> Self {
+        Self { names: (0..num_claims).map(|i| format!("claim{i}")).collect() }
+    }
+
+    pub fn fields(&self) -> Vec<JsonField<'_,'_>> {
+        self.names.iter().map(|name| JsonField::new_string(name, CLAIM_VALUE)).collect()
+    }
+
+    /// these claims as a map that serde can serialize, for comparing against jsonwebtoken
+    #[allow(dead_code)] // not every bench compares against jsonwebtoken
+    pub fn map(&self) -> BTreeMap<&str,&str> {
+        self.names.iter().map(|name| (name.as_str(), CLAIM_VALUE)).collect()
+    }
+
+    /// serialize these claims with the provided algorithm
+    pub fn token(&self, algorithm: JwtType) -> Vec<u8> {
+        let fields = self.fields();
+        let jwt = JsonWebToken::from_claims(&fields);
+        let mut token = vec![0_u8; jwt.serialized_len(algorithm)];
+        let n = jwt.serialize(token.as_mut_slice(), algorithm, SECRET).unwrap();
+        assert_eq!(token.len(), n);
+        token
+    }
+}
@@ -0,0 +1,163 @@
+//! prints the stack usage & instruction count of serialize_jwt & deserialize_jwt for each algorithm & claim set size.
+//! run with `cargo bench --bench footprint` & compare the output between revisions to catch regressions in the streaming writers.
+//!
+//! stack usage is measured by running each function on a stack that the harness allocates & paints beforehand, switching to it with psm,
+//! then counting the painted bytes that changed. it includes the closure & catch_unwind frames that wrap the function.
+//! instructions are counted with perf_event_open, so they are only available on Linux hosts that expose hardware counters
+
+use std::{hint::black_box, panic::{catch_unwind, resume_unwind, AssertUnwindSafe}};
+
+use psm::StackDirection;
+
+use lil_jwt::{deserialize_jwt, serialize_jwt, JsonWebToken, EMPTY_FIELD};
+
+mod common;
+use common::{ClaimSet, ALGORITHMS, CLAIM_COUNTS, SECRET};
+
+/// the size of each page of a MeasuredStack. page aligned stacks are portable across the targets psm supports
+const STACK_PAGE_LEN: usize = 4096;
+
+/// the number of pages of a MeasuredStack
+const STACK_PAGES: usize = 64;
+
+const STACK_PAINT: u8 = 0xA5;
+
+#[derive(Clone,Copy)]
+#[repr(align(4096))]
+struct StackPage([u8; STACK_PAGE_LEN]);
+
+/// a stack that the harness owns, which measured functions are run on so it can be painted & inspected without touching the thread's own stack
+struct MeasuredStack {
+    pages: Vec<StackPage>,
+}
+
+impl MeasuredStack {
+    fn new() -> Self {
+        Self { pages: vec![StackPage([STACK_PAINT; STACK_PAGE_LEN]); STACK_PAGES] }
+    }
+
+    /// run f on this stack & return the number of bytes of it that were used
+    fn measure<R>(&mut self, f: impl FnOnce() -> R) -> usize {
+        self.pages.fill(StackPage([STACK_PAINT; STACK_PAGE_LEN]));
+        let len = STACK_PAGE_LEN * STACK_PAGES;
+        let base = self.pages.as_mut_ptr().cast::<u8>();
+        // SAFETY: base is page aligned & points to len bytes that nothing else accesses until on_stack returns,
+        // len is a multiple of the page size & catch_unwind keeps panics from unwinding out of the callback
+        let result = unsafe { psm::on_stack(base, len, || catch_unwind(AssertUnwindSafe(f))) };
+        black_box(result.unwrap_or_else(|panic| resume_unwind(panic)));
+        let bytes = self.pages.iter().flat_map(|page| page.0);
+        let untouched = match StackDirection::new() {
+            StackDirection::Descending => bytes.take_while(|&b| b == STACK_PAINT).count(),
+            StackDirection::Ascending => bytes.rev().take_while(|&b| b == STACK_PAINT).count(),
+        };
+        len - untouched
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod instructions {
+    use std::{fs::File, hint::black_box, io::Read, os::fd::{AsRawFd, FromRawFd}};
+
+    use perf_event_open_sys::{bindings, ioctls, perf_event_open};
+
+    /// counts the instructions retired by this thread in user space
+    pub struct InstructionCounter {
+        counter: File,
+    }
+
+    impl InstructionCounter {
+        pub fn new() -> Option<Self> {
+            // SAFETY: perf_event_attr is plain old data
+            let mut attr: bindings::perf_event_attr = unsafe { core::mem::zeroed() };
+            attr.type_ = bindings::perf_type_id_PERF_TYPE_HARDWARE;
+            attr.size = core::mem::size_of::<bindings::perf_event_attr>() as u32;
+            attr.config = bindings::perf_hw_id_PERF_COUNT_HW_INSTRUCTIONS as u64;
+            attr.set_disabled(1);
+            attr.set_exclude_kernel(1);
+            attr.set_exclude_hv(1);
+            // SAFETY: attr is a valid perf_event_attr
+            let fd = unsafe { perf_event_open(&mut attr, 0, -1, -1, 0) };
+            if fd < 0 {
+                return None;
+            }
+            // SAFETY: fd is a newly opened file descriptor that nothing else owns
+            Some(Self { counter: unsafe { File::from_raw_fd(fd) } })
+        }
+
+        pub fn measure<R>(&mut self, f: impl FnOnce() -> R) -> u64 {
+            let fd = self.counter.as_raw_fd();
+            // SAFETY: fd is a perf event file descriptor
+            unsafe {
+                ioctls::RESET(fd, 0);
+                ioctls::ENABLE(fd, 0);
+            }
+            black_box(f());
+            // SAFETY: fd is a perf event file descriptor
+            unsafe { ioctls::DISABLE(fd, 0) };
+            let mut count = [0_u8; 8];
+            self.counter.read_exact(&mut count).unwrap();
+            u64::from_ne_bytes(count)
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod instructions {
+    pub struct InstructionCounter;
+
+    impl InstructionCounter {
+        pub fn new() -> Option<Self> {
+            None
+        }
+
+        pub fn measure<R>(&mut self, _f: impl FnOnce() -> R) -> u64 {
+            unreachable!()
+        }
+    }
+}
+
+use instructions::InstructionCounter;
+
+fn format_instructions(instructions: Option<u64>) -> String {
+    instructions.map_or_else(|| "n/a".into(), |n| n.to_string())
+}
+
+fn report() {
+    let mut stack = MeasuredStack::new();
+    let mut instruction_counter = InstructionCounter::new();
+    println!("{:<6} {:>7} {:>12} {:>15} {:>18} {:>17} {:>20}", "alg", "claims", "token bytes", "serialize stack", "serialize instrs", "deserialize stack", "deserialize instrs");
+    for (name, algorithm) in ALGORITHMS {
+        for num_claims in CLAIM_COUNTS {
+            let claim_set = ClaimSet::new(num_claims);
+            let claims = claim_set.fields();
+            let token = claim_set.token(algorithm);
+            let base64buffer_len = JsonWebToken::base64buffer_len(&token).unwrap();
+
+            let mut output = vec![0_u8; token.len()];
+            let mut serialize = || serialize_jwt(output.as_mut_slice(), black_box(&claims), &algorithm, SECRET).unwrap();
+            let serialize_stack = stack.measure(&mut serialize);
+            let serialize_instructions = instruction_counter.as_mut().map(|counter| counter.measure(&mut serialize));
+
+            // deserialized claims borrow the base64buffer, so each measurement gets its own buffers
+            let (mut base64buffer, mut claims_buffer) = (vec![0_u8; base64buffer_len], vec![EMPTY_FIELD; num_claims]);
+            let (token_ref, base64buffer_ref, claims_buffer_ref) = (token.as_slice(), base64buffer.as_mut_slice(), claims_buffer.as_mut_slice());
+            let deserialize_stack = stack.measure(move || deserialize_jwt(black_box(token_ref), claims_buffer_ref, &algorithm, SECRET, base64buffer_ref).unwrap());
+            let (mut base64buffer, mut claims_buffer) = (vec![0_u8; base64buffer_len], vec![EMPTY_FIELD; num_claims]);
+            let (token_ref, base64buffer_ref, claims_buffer_ref) = (token.as_slice(), base64buffer.as_mut_slice(), claims_buffer.as_mut_slice());
+            let deserialize_instructions = instruction_counter.as_mut().map(move |counter| counter.measure(move || deserialize_jwt(black_box(token_ref), claims_buffer_ref, &algorithm, SECRET, base64buffer_ref).unwrap()));
+
+            println!("{:<6} {:>7} {:>12} {:>15} {:>18} {:>17} {:>20}", name, num_claims, token.len(), serialize_stack, format_instructions(serialize_instructions), deserialize_stack, format_instructions(deserialize_instructions));
+        }
+    }
+    if instruction_counter.is_none() {
+        println!("instruction counts are unavailable: this host doesn't expose hardware performance counters");
+    }
+}
+
+fn main() {
+    // `cargo bench` passes --bench, `cargo test --benches` doesn't. only measure when benchmarking
+    if !std::env::args().any(|arg| arg == "--bench") {
+        return;
+    }
+    report();
+}
@@ -0,0 +1,111 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::collections::BTreeMap;
+
+use lil_jwt::{deserialize_jwt, serialize_jwt, JsonWebToken, JwtType, SignatureAlgorithm, EMPTY_FIELD};
+
+mod common;
+use common::{ClaimSet, ALGORITHMS, CLAIM_COUNTS, SECRET};
+
+fn serialize(c: &mut Criterion) {
+    for (name, algorithm) in ALGORITHMS {
+        let mut group = c.benchmark_group(format!("serialize_jwt/{name}"));
+        for num_claims in CLAIM_COUNTS {
+            let claim_set = ClaimSet::new(num_claims);
+            let claims = claim_set.fields();
+            let mut output = claim_set.token(algorithm);
+            group.throughput(Throughput::Bytes(output.len() as u64));
+            group.bench_function(BenchmarkId::from_parameter(format!("{num_claims}_claims")), |b| {
+                b.iter(|| serialize_jwt(output.as_mut_slice(), black_box(&claims), &algorithm, SECRET).unwrap())
+            });
+        }
+        group.finish();
+    }
+}
+
+/// deserialize a token with exactly N claims into a claims buffer on the stack, so the timed loop doesn't allocate.
+/// the claims borrow the base64buffer, so the claims buffer can't outlive a single iteration
+fn deserialize_claims<const N: usize>(token: &[u8], algorithm: &JwtType, base64buffer: &mut [u8]) {
+    let mut claims_buffer = [EMPTY_FIELD; N];
+    let n = deserialize_jwt(black_box(token), &mut claims_buffer, algorithm, SECRET, base64buffer).unwrap();
+    assert_eq!(N, n);
+}
+
+type DeserializeClaims = fn(&[u8], &JwtType, &mut [u8]);
+
+/// deserialize_claims for each of CLAIM_COUNTS
+const DESERIALIZE_CLAIMS: [DeserializeClaims; CLAIM_COUNTS.len()] = [deserialize_claims::<{ CLAIM_COUNTS[0] }>, deserialize_claims::<{ CLAIM_COUNTS[1] }>, deserialize_claims::<{ CLAIM_COUNTS[2] }>, deserialize_claims::<{ CLAIM_COUNTS[3] }>, deserialize_claims::<{ CLAIM_COUNTS[4] }>];
+
+fn deserialize(c: &mut Criterion) {
+    for (name, algorithm) in ALGORITHMS {
+        let mut group = c.benchmark_group(format!("deserialize_jwt/{name}"));
+        for (num_claims, deserialize_claims) in CLAIM_COUNTS.into_iter().zip(DESERIALIZE_CLAIMS) {
+            let claim_set = ClaimSet::new(num_claims);
+            let token = claim_set.token(algorithm);
+            let mut base64buffer = vec![0_u8; JsonWebToken::base64buffer_len(&token).unwrap()];
+            group.throughput(Throughput::Bytes(token.len() as u64));
+            group.bench_function(BenchmarkId::from_parameter(format!("{num_claims}_claims")), |b| {
+                b.iter(|| deserialize_claims(&token, &algorithm, &mut base64buffer))
+            });
+        }
+        group.finish();
+    }
+}
+
+/// the jsonwebtoken algorithm equivalent to an HMAC JwtType. jsonwebtoken doesn't support unsecured tokens
+fn jsonwebtoken_algorithm(algorithm: JwtType) -> Option<jsonwebtoken::Algorithm> {
+    match algorithm {
+        JwtType::Signed(SignatureAlgorithm::HS256) => Some(jsonwebtoken::Algorithm::HS256),
+        JwtType::Signed(SignatureAlgorithm::HS384) => Some(jsonwebtoken::Algorithm::HS384),
+        JwtType::Signed(SignatureAlgorithm::HS512) => Some(jsonwebtoken::Algorithm::HS512),
+        _ => None,
+    }
+}
+
+/// jsonwebtoken::encode with the same claims as serialize, for comparison
+fn serialize_jsonwebtoken(c: &mut Criterion) {
+    let key = jsonwebtoken::EncodingKey::from_secret(SECRET);
+    for (name, algorithm) in ALGORITHMS {
+        let Some(jsonwebtoken_algorithm) = jsonwebtoken_algorithm(algorithm) else {
+            continue;
+        };
+        let header = jsonwebtoken::Header::new(jsonwebtoken_algorithm);
+        let mut group = c.benchmark_group(format!("jsonwebtoken_encode/{name}"));
+        for num_claims in CLAIM_COUNTS {
+            let claim_set = ClaimSet::new(num_claims);
+            let claims = claim_set.map();
+            group.throughput(Throughput::Bytes(claim_set.token(algorithm).len() as u64));
+            group.bench_function(BenchmarkId::from_parameter(format!("{num_claims}_claims")), |b| {
+                b.iter(|| jsonwebtoken::encode(&header, black_box(&claims), &key).unwrap())
+            });
+        }
+        group.finish();
+    }
+}
+
+/// jsonwebtoken::decode of the tokens deserialize verifies, with its default claim checks turned off
+fn deserialize_jsonwebtoken(c: &mut Criterion) {
+    let key = jsonwebtoken::DecodingKey::from_secret(SECRET);
+    for (name, algorithm) in ALGORITHMS {
+        let Some(jsonwebtoken_algorithm) = jsonwebtoken_algorithm(algorithm) else {
+            continue;
+        };
+        let mut validation = jsonwebtoken::Validation::new(jsonwebtoken_algorithm);
+        validation.required_spec_claims.clear();
+        validation.validate_exp = false;
+        let mut group = c.benchmark_group(format!("jsonwebtoken_decode/{name}"));
+        for num_claims in CLAIM_COUNTS {
+            let token = String::from_utf8(ClaimSet::new(num_claims).token(algorithm)).unwrap();
+            group.throughput(Throughput::Bytes(token.len() as u64));
+            group.bench_function(BenchmarkId::from_parameter(format!("{num_claims}_claims")), |b| {
+                b.iter(|| {
+                    let decoded = jsonwebtoken::decode::<BTreeMap<String,String>>(black_box(&token), &key, &validation).unwrap();
+                    assert_eq!(num_claims, decoded.claims.len());
+                })
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, serialize, deserialize, serialize_jsonwebtoken, deserialize_jsonwebtoken);
+criterion_main!(benches);
@@ -1,5 +1,5 @@
 use lil_json::{JsonField,JsonValue};
-use lil_jwt::{JsonWebToken, JwtType};
+use lil_jwt::{InsecureNoVerification, JsonWebToken, JwtType};
 
 fn main() {
     let data = b"eyJhbGciOiJub25lIn0.eyJzdWIiOiIxMjM0NTY3ODkwIiwibmFtZSI6IkpvaG4gRG9lIiwiYWRtaW4iOnRydWUsImlhdCI6MTczNjI5MjEyNH0.";
@@ -7,7 +7,7 @@ fn main() {
     let claims = JsonWebToken::deserialize_claims::<10>(
         data,
         &mut base64buffer,
-        JwtType::Unsecured,
+        JwtType::Unsecured(InsecureNoVerification),
         b"ignored",
     ).unwrap();
     let claims_slice = claims.as_slice();
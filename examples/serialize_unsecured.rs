@@ -1,7 +1,7 @@
 use std::io::stdout;
 use embedded_io_adapters::std::FromStd;
 use lil_json::{JsonObject, JsonValue};
-use lil_jwt::JsonWebToken;
+use lil_jwt::{InsecureNoVerification, JsonWebToken};
 
 fn main() {
     let stdout = FromStd::new(stdout());
@@ -13,7 +13,7 @@ fn main() {
     JsonWebToken::from_claims(json_object.as_slice())
     .serialize(
         stdout,
-        lil_jwt::JwtType::Unsecured,
+        lil_jwt::JwtType::Unsecured(InsecureNoVerification),
         b"ignored",
     ).unwrap();
 }
\ No newline at end of file
@@ -0,0 +1,31 @@
+use lil_jwt::{FromClaims, InsecureNoVerification, JsonWebToken, JwtType, ToClaims, Validation, EMPTY_FIELD};
+
+#[derive(Debug,PartialEq,ToClaims,FromClaims)]
+struct DeviceClaims<'a> {
+    sub: &'a str,
+    exp: u64,
+    admin: bool,
+    scope: Option<&'a str>,
+}
+
+fn main() {
+    let claims = DeviceClaims { sub: "device-1234", exp: 1736292124, admin: false, scope: None };
+    let mut claims_buffer = [EMPTY_FIELD; DeviceClaims::MAX_CLAIMS];
+    let mut token = [0_u8; 256];
+    let n = JsonWebToken::from_typed(&claims, &mut claims_buffer)
+    .unwrap()
+    .serialize(
+        token.as_mut_slice(),
+        JwtType::Unsecured(InsecureNoVerification),
+        b"ignored",
+    ).unwrap();
+    let mut base64buffer = [0_u8; 256];
+    let decoded = JsonWebToken::deserialize_typed::<DeviceClaims, 4>(
+        &token[..n],
+        &mut base64buffer,
+        JwtType::Unsecured(InsecureNoVerification),
+        b"ignored",
+        &Validation::new(),
+    ).unwrap();
+    assert_eq!(claims, decoded);
+}
@@ -0,0 +1,9 @@
+use std::{env, fs, path::PathBuf};
+
+fn main() {
+    // put memory.x where the cortex-m-rt linker script can find it
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    fs::write(out_dir.join("memory.x"), include_bytes!("memory.x")).unwrap();
+    println!("cargo:rustc-link-search={}", out_dir.display());
+    println!("cargo:rerun-if-changed=memory.x");
+}
@@ -0,0 +1,6 @@
+/* a typical Cortex-M4F part, e.g. STM32F411 */
+MEMORY
+{
+  FLASH : ORIGIN = 0x08000000, LENGTH = 512K
+  RAM : ORIGIN = 0x20000000, LENGTH = 128K
+}
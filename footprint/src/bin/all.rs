@@ -0,0 +1,16 @@
+#![no_std]
+#![no_main]
+
+use lil_jwt::{InsecureNoVerification, JwtType, SignatureAlgorithm};
+use panic_halt as _;
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    lil_jwt_footprint::run(&[
+        JwtType::Unsecured(InsecureNoVerification),
+        JwtType::Signed(SignatureAlgorithm::HS256),
+        JwtType::Signed(SignatureAlgorithm::HS384),
+        JwtType::Signed(SignatureAlgorithm::HS512),
+    ]);
+    loop {}
+}
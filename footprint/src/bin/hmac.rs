@@ -0,0 +1,15 @@
+#![no_std]
+#![no_main]
+
+use lil_jwt::{JwtType, SignatureAlgorithm};
+use panic_halt as _;
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    lil_jwt_footprint::run(&[
+        JwtType::Signed(SignatureAlgorithm::HS256),
+        JwtType::Signed(SignatureAlgorithm::HS384),
+        JwtType::Signed(SignatureAlgorithm::HS512),
+    ]);
+    loop {}
+}
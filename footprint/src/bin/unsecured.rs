@@ -0,0 +1,11 @@
+#![no_std]
+#![no_main]
+
+use lil_jwt::{InsecureNoVerification, JwtType};
+use panic_halt as _;
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    lil_jwt_footprint::run(&[JwtType::Unsecured(InsecureNoVerification)]);
+    loop {}
+}
@@ -0,0 +1,42 @@
+//! the code shared by the sample binaries. each binary serializes & deserializes a small token with the algorithms it uses,
+//! so the linker keeps only what those algorithms need
+
+#![no_std]
+
+use core::hint::black_box;
+
+use lil_jwt::{deserialize_jwt, serialize_jwt, JsonField, JsonValue, JwtType, EMPTY_FIELD};
+
+const SECRET: &[u8] = b"a-string-secret-at-least-256-bits-long";
+
+const NUM_CLAIMS: usize = 4;
+
+static CLAIMS: [JsonField<'static,'static>; NUM_CLAIMS] = [
+    JsonField { key: "sub", value: JsonValue::String("1234567890") },
+    JsonField { key: "name", value: JsonValue::String("John Doe") },
+    JsonField { key: "admin", value: JsonValue::Boolean(true) },
+    JsonField { key: "iat", value: JsonValue::Number(1516239022) },
+];
+
+/// the stack usage of this function is reported as the stack usage of serialize_jwt
+#[inline(never)]
+pub fn sample_serialize(output: &mut [u8], algorithm: &JwtType) -> usize {
+    serialize_jwt(output, black_box(&CLAIMS), algorithm, SECRET).unwrap_or(0)
+}
+
+/// the stack usage of this function is reported as the stack usage of deserialize_jwt
+#[inline(never)]
+pub fn sample_deserialize<'a>(token: &'a [u8], claims_buffer: &mut [JsonField<'a,'a>], base64buffer: &'a mut [u8], algorithm: &JwtType) -> usize {
+    deserialize_jwt(black_box(token), claims_buffer, algorithm, SECRET, base64buffer).unwrap_or(0)
+}
+
+/// serialize & deserialize a token with each of the provided algorithms
+pub fn run(algorithms: &[JwtType]) {
+    for algorithm in algorithms {
+        let mut token = [0_u8; 256];
+        let n = sample_serialize(&mut token, algorithm);
+        let mut base64buffer = [0_u8; 256];
+        let mut claims_buffer = [EMPTY_FIELD; NUM_CLAIMS];
+        black_box(sample_deserialize(&token[..n], &mut claims_buffer, &mut base64buffer, algorithm));
+    }
+}
@@ -0,0 +1,107 @@
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, FieldsNamed, Lifetime, LitStr};
+
+/// derive `lil_jwt::ToClaims` for a struct with named fields, writing each field as a claim with the same name
+#[proc_macro_derive(ToClaims)]
+pub fn derive_to_claims(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_to_claims(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// derive `lil_jwt::FromClaims` for a struct with named fields, reading each field from the claim with the same name
+#[proc_macro_derive(FromClaims)]
+pub fn derive_from_claims(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_from_claims(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn named_fields(input: &DeriveInput) -> Result<&FieldsNamed, Error> {
+    match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => Ok(fields),
+            _ => Err(Error::new_spanned(&input.ident, "claims can only be derived for structs with named fields")),
+        },
+        _ => Err(Error::new_spanned(&input.ident, "claims can only be derived for structs")),
+    }
+}
+
+/// the single lifetime parameter of the struct, if any. type & const parameters are not supported
+fn claims_lifetime(input: &DeriveInput) -> Result<Option<&Lifetime>, Error> {
+    if input.generics.type_params().next().is_some() || input.generics.const_params().next().is_some() {
+        return Err(Error::new_spanned(&input.generics, "claims can only be derived for structs without type or const parameters"));
+    }
+    let mut lifetimes = input.generics.lifetimes();
+    let lifetime = lifetimes.next().map(|l| &l.lifetime);
+    if lifetimes.next().is_some() {
+        return Err(Error::new_spanned(&input.generics, "claims can only be derived for structs with at most one lifetime parameter"));
+    }
+    Ok(lifetime)
+}
+
+fn claim_name(field: &syn::Field) -> LitStr {
+    let ident = field.ident.as_ref().expect("named field");
+    let name = ident.to_string();
+    LitStr::new(name.strip_prefix("r#").unwrap_or(&name), ident.span())
+}
+
+fn expand_to_claims(input: &DeriveInput) -> Result<proc_macro2::TokenStream, Error> {
+    let fields = named_fields(input)?;
+    claims_lifetime(input)?;
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let max_claims = fields.named.len();
+    let pushes = fields.named.iter().map(|field| {
+        let member = field.ident.as_ref().expect("named field");
+        let name = claim_name(field);
+        quote! {
+            if let ::core::option::Option::Some(value) = ::lil_jwt::ToClaimValue::to_claim_value(&self.#member, #name)? {
+                *claims_buffer.get_mut(num_claims).ok_or(::lil_jwt::ToClaimsFailure::BufferTooSmall)? = ::lil_jwt::JsonField::new(#name, value);
+                num_claims += 1;
+            }
+        }
+    });
+    Ok(quote! {
+        impl #impl_generics ::lil_jwt::ToClaims for #ident #ty_generics #where_clause {
+            const MAX_CLAIMS: usize = #max_claims;
+            fn to_claims<'__claims>(&'__claims self, claims_buffer: &mut [::lil_jwt::JsonField<'__claims,'__claims>]) -> ::core::result::Result<usize,::lil_jwt::ToClaimsFailure> {
+                let mut num_claims = 0;
+                #(#pushes)*
+                ::core::result::Result::Ok(num_claims)
+            }
+        }
+    })
+}
+
+fn expand_from_claims(input: &DeriveInput) -> Result<proc_macro2::TokenStream, Error> {
+    let fields = named_fields(input)?;
+    let ident = &input.ident;
+    let (_, ty_generics, where_clause) = input.generics.split_for_impl();
+    let lifetime = match claims_lifetime(input)? {
+        Some(lifetime) => lifetime.clone(),
+        None => Lifetime::new("'__claims", Span::call_site()),
+    };
+    let reads = fields.named.iter().map(|field| {
+        let member = field.ident.as_ref().expect("named field");
+        let name = claim_name(field);
+        quote! {
+            #member: ::lil_jwt::FromClaimValue::from_claim_value(::lil_jwt::get_claim(claims, #name), #name)?,
+        }
+    });
+    Ok(quote! {
+        impl<#lifetime> ::lil_jwt::FromClaims<#lifetime> for #ident #ty_generics #where_clause {
+            fn from_claims(claims: &[::lil_jwt::JsonField<#lifetime,#lifetime>]) -> ::core::result::Result<Self,::lil_jwt::JwtParseFailure> {
+                ::core::result::Result::Ok(Self {
+                    #(#reads)*
+                })
+            }
+        }
+    })
+}
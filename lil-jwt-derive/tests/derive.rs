@@ -0,0 +1,88 @@
+use lil_jwt::{FromClaims, InsecureNoVerification, JsonValue, JsonWebToken, JwtParseFailure, JwtType, ToClaims, ToClaimsFailure, Validation, EMPTY_FIELD};
+
+#[derive(Debug,PartialEq,ToClaims,FromClaims)]
+struct BorrowedClaims<'a> {
+    sub: &'a str,
+    exp: u64,
+    scope: Option<&'a str>,
+}
+
+#[derive(Debug,PartialEq,ToClaims,FromClaims)]
+struct OwnedClaims {
+    exp: u64,
+    admin: bool,
+    r#type: Option<i32>,
+}
+
+fn round_trip<'a, T: ToClaims + FromClaims<'a>, const MAX_CLAIMS: usize>(claims: &T, token: &'a mut [u8], base64buffer: &'a mut [u8]) -> T {
+    let mut claims_buffer = [EMPTY_FIELD; MAX_CLAIMS];
+    let n = JsonWebToken::from_typed(claims, &mut claims_buffer).unwrap().serialize(&mut *token, JwtType::Unsecured(InsecureNoVerification), &[]).unwrap();
+    JsonWebToken::deserialize_typed::<T, MAX_CLAIMS>(&token[..n], base64buffer, JwtType::Unsecured(InsecureNoVerification), &[], &Validation::new()).unwrap()
+}
+
+#[test]
+fn test_max_claims() {
+    assert_eq!(3, BorrowedClaims::MAX_CLAIMS);
+    assert_eq!(3, OwnedClaims::MAX_CLAIMS);
+}
+
+#[test]
+fn test_borrowed_round_trip() {
+    let (mut token, mut base64buffer) = ([0_u8; 256], [0_u8; 256]);
+    for claims in [BorrowedClaims { sub: "device-1", exp: 1736292124, scope: Some("read") }, BorrowedClaims { sub: "device-2", exp: 0, scope: None }] {
+        assert_eq!(claims, round_trip::<_, 3>(&claims, &mut token, &mut base64buffer));
+    }
+}
+
+#[test]
+fn test_owned_round_trip() {
+    let (mut token, mut base64buffer) = ([0_u8; 256], [0_u8; 256]);
+    for claims in [OwnedClaims { exp: 1736292124, admin: true, r#type: Some(-7) }, OwnedClaims { exp: 1, admin: false, r#type: None }] {
+        assert_eq!(claims, round_trip::<_, 3>(&claims, &mut token, &mut base64buffer));
+    }
+}
+
+#[test]
+fn test_to_claims() {
+    let claims = OwnedClaims { exp: 1, admin: false, r#type: Some(2) };
+    let mut claims_buffer = [EMPTY_FIELD; OwnedClaims::MAX_CLAIMS];
+    assert_eq!(Ok(3), claims.to_claims(&mut claims_buffer));
+    assert_eq!(["exp", "admin", "type"], claims_buffer.map(|claim| claim.key));
+    assert!(matches!(claims_buffer[2].value, JsonValue::Number(2)));
+    let claims = BorrowedClaims { sub: "device-1", exp: 1, scope: None };
+    let mut claims_buffer = [EMPTY_FIELD; BorrowedClaims::MAX_CLAIMS];
+    assert_eq!(Ok(2), claims.to_claims(&mut claims_buffer));
+    assert_eq!(["sub", "exp"], [claims_buffer[0].key, claims_buffer[1].key]);
+}
+
+#[test]
+fn test_from_claims_invalid() {
+    let mut claims_buffer = [EMPTY_FIELD; 3];
+    claims_buffer[0] = lil_jwt::JsonField::new("exp", JsonValue::Number(1));
+    claims_buffer[1] = lil_jwt::JsonField::new("admin", JsonValue::Boolean(true));
+    claims_buffer[2] = lil_jwt::JsonField::new("type", JsonValue::String("admin"));
+    assert!(matches!(OwnedClaims::from_claims(&claims_buffer), Err(JwtParseFailure::InvalidClaim("type"))));
+    assert!(matches!(OwnedClaims::from_claims(&claims_buffer[..1]), Err(JwtParseFailure::MissingClaim("admin"))));
+    assert_eq!(OwnedClaims { exp: 1, admin: true, r#type: None }, OwnedClaims::from_claims(&claims_buffer[..2]).unwrap());
+    assert!(matches!(BorrowedClaims::from_claims(&claims_buffer[..1]), Err(JwtParseFailure::MissingClaim("sub"))));
+}
+
+#[test]
+fn test_from_typed_short_buffer() {
+    let claims = OwnedClaims { exp: 1, admin: false, r#type: None };
+    let mut claims_buffer = [EMPTY_FIELD; OwnedClaims::MAX_CLAIMS - 1];
+    assert!(matches!(JsonWebToken::from_typed(&claims, &mut claims_buffer), Err(ToClaimsFailure::BufferTooSmall)));
+    let claims = OwnedClaims { exp: 1, admin: false, r#type: Some(3) };
+    let mut claims_buffer = [EMPTY_FIELD; OwnedClaims::MAX_CLAIMS - 1];
+    assert_eq!(Err(ToClaimsFailure::BufferTooSmall), claims.to_claims(&mut claims_buffer));
+}
+
+#[test]
+fn test_to_claims_out_of_range() {
+    let claims = OwnedClaims { exp: u64::MAX, admin: false, r#type: None };
+    let mut claims_buffer = [EMPTY_FIELD; OwnedClaims::MAX_CLAIMS];
+    assert!(matches!(JsonWebToken::from_typed(&claims, &mut claims_buffer), Err(ToClaimsFailure::InvalidClaim("exp"))));
+    let claims = OwnedClaims { exp: i64::MAX as u64, admin: false, r#type: None };
+    let mut claims_buffer = [EMPTY_FIELD; OwnedClaims::MAX_CLAIMS];
+    assert!(JsonWebToken::from_typed(&claims, &mut claims_buffer).is_ok());
+}
@@ -0,0 +1,59 @@
+use lil_json::{parse_json_object, JsonField, JsonValue};
+
+use crate::{decode_segment, parse_claims, split_jwt_parts, verify_jose_header, verify_signature, JwtParseFailure, JwtSegment, JwtType, Validation, EMPTY_FIELD, MAX_HEADER_FIELDS};
+
+/// a key that tokens signed with a specific algorithm may be verified with.
+/// pairing each key with its algorithm means a key is never used with an algorithm it was not meant for, e.g. an asymmetric public key as an HMAC secret
+#[derive(Debug,Clone,Copy)]
+pub struct VerificationKey<'k> {
+    algorithm: JwtType,
+    secret: &'k [u8],
+}
+
+impl<'k> VerificationKey<'k> {
+
+    /// allow tokens signed with algorithm to be verified with secret
+    pub const fn new(algorithm: JwtType, secret: &'k [u8]) -> Self {
+        Self { algorithm, secret }
+    }
+
+    /// the algorithm this key verifies
+    pub const fn algorithm(&self) -> JwtType {
+        self.algorithm
+    }
+}
+
+/// deserialize a JWT whose algorithm is read from its `alg` header, accepting it only if it verifies with one of the allowed keys for that algorithm.
+/// keys are tried in order, so several keys for the same algorithm can be allowed during key rotation.
+/// returns the algorithm the JWT was verified with & the number of claims
+pub fn deserialize_jwt_allowed<'a>(data: &'a [u8], claims_buffer: &mut [JsonField<'a,'a>], allowed: &[VerificationKey<'_>], base64buffer: &'a mut [u8], validation: &Validation<'_>) -> Result<(JwtType,usize),JwtParseFailure> {
+    let (header_b64,body_b64,signature_b64) = split_jwt_parts(data)?;
+    let header_decoded_end = decode_segment(JwtSegment::Header, header_b64, base64buffer)?;
+    let (decoded_header,remaining_base64_buffer) = base64buffer.split_at_mut(header_decoded_end);
+    let mut header_buffer = [EMPTY_FIELD; MAX_HEADER_FIELDS];
+    let header_fields = match parse_json_object(&*decoded_header, &mut header_buffer) {
+        Ok((_num_bytes,n)) => header_buffer.split_at(n).0,
+        Err(j) => return Err(JwtParseFailure::InvalidHeader(j)),
+    };
+    let algorithm = header_algorithm(header_fields)?;
+    verify_jose_header(header_fields, &algorithm, validation)?;
+    let mut keys = allowed.iter().filter(|key| key.algorithm == algorithm).peekable();
+    if keys.peek().is_none() {
+        return Err(JwtParseFailure::DisallowedAlgorithm);
+    }
+    if !keys.any(|key| verify_signature(header_b64, body_b64, signature_b64, &algorithm, key.secret).is_ok()) {
+        return Err(JwtParseFailure::InvalidSignature);
+    }
+    let body_decoded_end = decode_segment(JwtSegment::Payload, body_b64, remaining_base64_buffer)?;
+    let (num_claims,audience_array) = parse_claims(remaining_base64_buffer.split_at_mut(body_decoded_end).0, claims_buffer)?;
+    validation.validate_claims(claims_buffer.split_at(num_claims).0, audience_array)?;
+    Ok((algorithm,num_claims))
+}
+
+/// the algorithm named by the `alg` header, which is not trusted until the signature has been verified
+fn header_algorithm(header_fields: &[JsonField<'_,'_>]) -> Result<JwtType,JwtParseFailure> {
+    match header_fields.iter().find(|field| field.key == "alg").map(|field| field.value) {
+        Some(JsonValue::String(alg)) => JwtType::from_string(alg).ok_or(JwtParseFailure::DisallowedAlgorithm),
+        _ => Err(JwtParseFailure::IncorrectHeader),
+    }
+}
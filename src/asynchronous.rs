@@ -0,0 +1,166 @@
+use embedded_io::ErrorType;
+use embedded_io_async::Write;
+use lil_json::{serialize_json_object, JsonField};
+
+#[cfg(feature = "signature")]
+use hmac::{Hmac, Mac};
+#[cfg(feature = "signature")]
+use sha2::{Sha256, Sha384, Sha512};
+
+use crate::{base64_writer::Base64UrlBlockEncoder, default_typ, get_jose_header, JsonWebToken, JwtType};
+#[cfg(feature = "signature")]
+use crate::{authenticated_writer::AuthenticatedWriter, SignatureAlgorithm};
+
+/// the number of bytes of serialized JSON that are held at a time unless a larger buffer is provided.
+/// typical claims are shorter than this, so each of them is serialized once
+const JSON_CHUNK_SIZE: usize = 128;
+
+/// the window of a WindowWriter is full, so the rest of the pass can be skipped
+#[derive(Debug)]
+struct WindowFull;
+
+impl embedded_io::Error for WindowFull {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        embedded_io::ErrorKind::OutOfMemory
+    }
+}
+
+/// a blocking writer that keeps the window of its input that starts after skip bytes & stops at the end of the window
+struct WindowWriter<'w> {
+    skip: usize,
+    window: &'w mut [u8],
+    len: usize,
+}
+
+impl ErrorType for WindowWriter<'_> {
+    type Error = WindowFull;
+}
+
+impl embedded_io::Write for WindowWriter<'_> {
+    fn write(&mut self, data: &[u8]) -> Result<usize, Self::Error> {
+        let skipped = self.skip.min(data.len());
+        self.skip -= skipped;
+        let remaining = data.split_at(skipped).1;
+        if !remaining.is_empty() && self.len == self.window.len() {
+            return Err(WindowFull);
+        }
+        let n = remaining.len().min(self.window.len() - self.len);
+        self.window[self.len..self.len + n].copy_from_slice(remaining.split_at(n).0);
+        self.len += n;
+        Ok(skipped + n)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// write a JSON object to an async writer, holding at most chunk.len() bytes of it at a time.
+/// lil-json only serializes to blocking writers, so each field is serialized on its own & only a field longer than the chunk is serialized more than once
+async fn write_json_object<T: Write>(mut output: T, fields: &[JsonField<'_,'_>], chunk: &mut [u8]) -> Result<usize,T::Error> {
+    output.write_all(b"{").await?;
+    let mut ret = 1;
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            output.write_all(b",").await?;
+            ret += 1;
+        }
+        ret += write_json_member(&mut output, field, chunk).await?;
+    }
+    output.write_all(b"}").await?;
+    Ok(ret + 1)
+}
+
+/// write the `"name":value` member of a single field, which is the JSON object of that field without its braces.
+/// the field is serialized again for each chunk, which must not be empty, & each pass stops once the chunk is full
+async fn write_json_member<T: Write>(mut output: T, field: &JsonField<'_,'_>, chunk: &mut [u8]) -> Result<usize,T::Error> {
+    let mut ret = 0;
+    loop {
+        let mut window = WindowWriter { skip: 1 + ret, window: &mut *chunk, len: 0 };
+        let complete = serialize_json_object(&mut window, core::slice::from_ref(field)).is_ok();
+        // the closing brace is always the last byte of the final pass
+        let n = if complete { window.len - 1 } else { window.len };
+        output.write_all(chunk.split_at(n).0).await?;
+        ret += n;
+        if complete {
+            return Ok(ret);
+        }
+    }
+}
+
+async fn serialize_object_base64_async<T: Write>(output: T, fields: &[JsonField<'_,'_>], chunk: &mut [u8]) -> Result<usize,T::Error> {
+    let mut object_encoder = Base64UrlBlockEncoder::<_>::new(output);
+    write_json_object(&mut object_encoder, fields, chunk).await?;
+    object_encoder.finalize_async(false).await
+}
+
+#[cfg(feature = "signature")]
+async fn serialize_slice_base64_async<T: Write>(output: T, slice: &[u8]) -> Result<usize,T::Error> {
+    let mut slice_encoder = Base64UrlBlockEncoder::<_>::new(output);
+    slice_encoder.write_all(slice).await?;
+    slice_encoder.finalize_async(false).await
+}
+
+/// serialize a JWT to an async writer, streaming the token as it is signed.
+/// a claim longer than 128 bytes of JSON is serialized again for each 128 byte chunk, see JsonWebToken::serialize_async_with_buffer for long claims
+pub async fn serialize_jwt_async<T: Write>(output: T, claims: &[JsonField<'_,'_>], algorithm: &JwtType, secret: &[u8]) -> Result<usize,T::Error> {
+    let header = get_jose_header(default_typ(algorithm), None, algorithm);
+    serialize_jwt_with_header_async(output, header.as_slice(), claims, algorithm, secret, &mut [0_u8; JSON_CHUNK_SIZE]).await
+}
+
+async fn serialize_jwt_with_header_async<T: Write>(output: T, header: &[JsonField<'_,'_>], claims: &[JsonField<'_,'_>], algorithm: &JwtType, #[cfg_attr(not(feature = "signature"), allow(unused_variables))] secret: &[u8], chunk: &mut [u8]) -> Result<usize,T::Error> {
+    match algorithm {
+        #[cfg(feature = "unsecured")]
+        JwtType::Unsecured(_) => serialize_unsecured_async(output, header, claims, chunk).await,
+        #[cfg(feature = "signature")]
+        JwtType::Signed(SignatureAlgorithm::HS256) => serialize_signed_async(output, Hmac::<Sha256>::new_from_slice(secret).expect("invalid HS256 secret"), header, claims, chunk).await,
+        #[cfg(feature = "signature")]
+        JwtType::Signed(SignatureAlgorithm::HS384) => serialize_signed_async(output, Hmac::<Sha384>::new_from_slice(secret).expect("invalid HS384 secret"), header, claims, chunk).await,
+        #[cfg(feature = "signature")]
+        JwtType::Signed(SignatureAlgorithm::HS512) => serialize_signed_async(output, Hmac::<Sha512>::new_from_slice(secret).expect("invalid HS512 secret"), header, claims, chunk).await,
+        JwtType::Encrypted(e) => match *e {},
+    }
+}
+
+#[cfg(feature = "unsecured")]
+async fn serialize_unsecured_async<T: Write>(mut output: T, header: &[JsonField<'_,'_>], claims: &[JsonField<'_,'_>], chunk: &mut [u8]) -> Result<usize,T::Error> {
+    let mut ret = serialize_object_base64_async(&mut output, header, chunk).await?;
+    output.write_all(b".").await?;
+    ret += 1;
+    ret += serialize_object_base64_async(&mut output, claims, chunk).await?;
+    output.write_all(b".").await?;
+    ret += 1;
+    Ok(ret)
+}
+
+#[cfg(feature = "signature")]
+async fn serialize_signed_async<T: Write, D: Mac + hmac::digest::Update>(mut output: T, digest: D, header: &[JsonField<'_,'_>], claims: &[JsonField<'_,'_>], chunk: &mut [u8]) -> Result<usize,T::Error> {
+    let mut authenticated_writer = AuthenticatedWriter::new(&mut output, digest);
+    let mut ret = serialize_object_base64_async(&mut authenticated_writer, header, chunk).await?;
+    authenticated_writer.write_all(b".").await?;
+    ret += 1;
+    ret += serialize_object_base64_async(&mut authenticated_writer, claims, chunk).await?;
+    let mac = authenticated_writer.finalize_mac();
+    output.write_all(b".").await?;
+    ret += 1;
+    ret += serialize_slice_base64_async(&mut output, &mac.into_bytes()).await?;
+    Ok(ret)
+}
+
+impl JsonWebToken<'_> {
+
+    /// serialize this JsonWebToken with the specified algorithm & secret into the provided async output.
+    /// a claim longer than 128 bytes of JSON is serialized again for each 128 byte chunk of it.
+    /// serialize_async_with_buffer takes fewer passes over long claims
+    pub async fn serialize_async<T: Write>(&self, output: T, algorithm: JwtType, secret: &[u8]) -> Result<usize,T::Error> {
+        self.serialize_async_with_buffer(output, algorithm, secret, &mut [0_u8; JSON_CHUNK_SIZE]).await
+    }
+
+    /// serialize this JsonWebToken with the specified algorithm & secret into the provided async output, holding up to json_buffer.len() bytes of JSON at a time.
+    /// claims that fit in json_buffer are serialized once. an empty json_buffer fails to compile
+    pub async fn serialize_async_with_buffer<T: Write, const N: usize>(&self, output: T, algorithm: JwtType, secret: &[u8], json_buffer: &mut [u8; N]) -> Result<usize,T::Error> {
+        const { assert!(N > 0, "the JSON buffer must not be empty") };
+        let header = get_jose_header(self.typ.or(default_typ(&algorithm)), self.kid, &algorithm);
+        serialize_jwt_with_header_async(output, header.as_slice(), self.claims, &algorithm, secret, json_buffer).await
+    }
+}
@@ -1,5 +1,5 @@
 use embedded_io::{ErrorType, Write};
-use hmac::{digest::{self, CtOutput, Update}, Mac};
+use hmac::{digest::{self, CtOutput}, Mac};
 
 pub struct AuthenticatedWriter<T, D> {
     digest: D,
@@ -27,7 +27,19 @@ impl<T: Write,D: digest::Update> Write for AuthenticatedWriter<T,D> {
     }
 }
 
-impl<T: Write,D: Mac> AuthenticatedWriter<T,D> {
+#[cfg(feature = "async")]
+impl<T: embedded_io_async::Write,D: digest::Update> embedded_io_async::Write for AuthenticatedWriter<T,D> {
+    async fn flush(&mut self) -> Result<(), <Self as ErrorType>::Error> {
+        self.output.flush().await
+    }
+    async fn write(&mut self, data: &[u8]) -> Result<usize, <Self as ErrorType>::Error> {
+        let n = self.output.write(data).await?;
+        self.digest.update(data.split_at(n).0);
+        Ok(n)
+    }
+}
+
+impl<T,D: Mac> AuthenticatedWriter<T,D> {
     pub fn finalize_mac(self) -> CtOutput<D> {
         self.digest.finalize()
     }
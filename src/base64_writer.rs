@@ -1,101 +1,417 @@
-use base64::{prelude::{BASE64_URL_SAFE, BASE64_URL_SAFE_NO_PAD}, Engine};
-use embedded_io::{ErrorType, Write};
+use base64::{prelude::{BASE64_URL_SAFE, BASE64_URL_SAFE_NO_PAD}, DecodeError, Engine};
+use embedded_io::{ErrorKind, ErrorType, Write};
 
+/// the number of input bytes in a base64 block
 pub const BASE64_INPUT_BLOCK_SIZE: usize = 3;
+/// the number of encoded bytes in a base64 block
 pub const BASE64_OUTPUT_BLOCK_SIZE: usize = 4;
 
-#[derive(Default)]
-pub struct Base64UrlBlockEncoder<T> {
+/// the base64url alphabet
+const BASE64_URL_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// encode a single whole block. this is cheaper than going through the base64 engine for the handful of bytes in a typical write
+fn encode_block(input: [u8; BASE64_INPUT_BLOCK_SIZE]) -> [u8; BASE64_OUTPUT_BLOCK_SIZE] {
+    let n = u32::from_be_bytes([0, input[0], input[1], input[2]]);
+    [
+        BASE64_URL_ALPHABET[(n >> 18) as usize & 0x3f],
+        BASE64_URL_ALPHABET[(n >> 12) as usize & 0x3f],
+        BASE64_URL_ALPHABET[(n >> 6) as usize & 0x3f],
+        BASE64_URL_ALPHABET[n as usize & 0x3f],
+    ]
+}
+
+/// the number of blocks buffered by a Base64UrlBlockEncoder unless otherwise specified
+pub const DEFAULT_MAX_BLOCKS: usize = 16;
+
+/// a writer that base64url encodes its input into an inner writer.
+/// encoded blocks are buffered & only written to the inner writer by the first write after MAX_BLOCKS blocks are ready, when flushed or when finalized.
+/// if the inner writer fails, write consumes none of its input so it can be retried
+/// more blocks use more stack space but make fewer, larger writes to the inner writer.
+/// the trailing partial block is only encoded by finalize, so the encoder must be finalized once all data has been written
+pub struct Base64UrlBlockEncoder<T, const MAX_BLOCKS: usize = DEFAULT_MAX_BLOCKS> {
     inner: T,
     input_buffer: [u8; BASE64_INPUT_BLOCK_SIZE],
-    output_buffer: [u8; BASE64_OUTPUT_BLOCK_SIZE],
+    output_buffer: [[u8; BASE64_OUTPUT_BLOCK_SIZE]; MAX_BLOCKS],
     input_position: usize,
+    output_blocks: usize,
     total_bytes_out: usize,
 }
 
-impl<T: Write> Base64UrlBlockEncoder<T> {
+impl<T, const MAX_BLOCKS: usize> Base64UrlBlockEncoder<T, MAX_BLOCKS> {
 
-    // TODO: make the number of blocks generic
-    // pub const MAX_BLOCKS: usize = 1;
+    /// the number of blocks buffered before they are written to the inner writer
+    pub const MAX_BLOCKS: usize = MAX_BLOCKS;
 
-    // pub const MAX_INPUT_BYTES: usize = {
-    //     Self::MAX_BLOCKS * BASE64_INPUT_BLOCK_SIZE
-    // };
+    /// the number of input bytes encoded into a full output buffer
+    pub const MAX_INPUT_BYTES: usize = MAX_BLOCKS * BASE64_INPUT_BLOCK_SIZE;
 
-    // pub const MAX_OUTPUT_BYTES: usize = {
-    //     Self::MAX_BLOCKS * BASE64_OUTPUT_BLOCK_SIZE
-    // };
+    /// the size of the output buffer, which is the largest write made to the inner writer
+    pub const MAX_OUTPUT_BYTES: usize = MAX_BLOCKS * BASE64_OUTPUT_BLOCK_SIZE;
 
+    const AT_LEAST_ONE_BLOCK: () = assert!(MAX_BLOCKS > 0, "a Base64UrlBlockEncoder must buffer at least one block");
+
+    /// construct an encoder that writes to the provided inner writer
     pub fn new(inner: T) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::AT_LEAST_ONE_BLOCK;
         Self {
             inner,
             input_buffer: [0_u8; BASE64_INPUT_BLOCK_SIZE],
-            output_buffer: [0_u8; BASE64_OUTPUT_BLOCK_SIZE],
+            output_buffer: [[0_u8; BASE64_OUTPUT_BLOCK_SIZE]; MAX_BLOCKS],
             input_position: 0_usize,
-            
+            output_blocks: 0_usize,
             total_bytes_out: 0,
         }
     }
 
+    fn is_output_full(&self) -> bool {
+        self.output_blocks == MAX_BLOCKS
+    }
+
+    fn output_written(&mut self) {
+        self.total_bytes_out += self.output_blocks * BASE64_OUTPUT_BLOCK_SIZE;
+        self.output_blocks = 0;
+    }
+
+    /// encode as many whole blocks of data as fit in the output buffer, carrying any trailing partial block over to the next call.
+    /// the output buffer must not be full. returns the number of bytes of data consumed
+    #[inline]
+    fn encode_blocks(&mut self, data: &[u8]) -> usize {
+        if data.len() < BASE64_INPUT_BLOCK_SIZE {
+            // lil-json writes strings a character at a time, so keep small writes cheap.
+            // they complete at most one block & the output buffer has room for one
+            for &byte in data {
+                self.input_buffer[self.input_position] = byte;
+                self.input_position += 1;
+                if self.input_position == BASE64_INPUT_BLOCK_SIZE {
+                    self.output_buffer[self.output_blocks] = encode_block(self.input_buffer);
+                    self.output_blocks += 1;
+                    self.input_position = 0;
+                }
+            }
+            return data.len();
+        }
+        self.encode_whole_blocks(data)
+    }
+
+    #[inline(never)]
+    fn encode_whole_blocks(&mut self, data: &[u8]) -> usize {
+        let mut consumed = 0;
+        // complete the block carried over from the previous call
+        while self.input_position > 0 {
+            let Some(&byte) = data.get(consumed) else {
+                return consumed;
+            };
+            self.input_buffer[self.input_position] = byte;
+            self.input_position = (self.input_position + 1) % BASE64_INPUT_BLOCK_SIZE;
+            consumed += 1;
+            if self.input_position == 0 {
+                self.output_buffer[self.output_blocks] = encode_block(self.input_buffer);
+                self.output_blocks += 1;
+            }
+        }
+        let mut input_blocks = data[consumed..].chunks_exact(BASE64_INPUT_BLOCK_SIZE);
+        for (output_block, input_block) in self.output_buffer[self.output_blocks..].iter_mut().zip(&mut input_blocks) {
+            *output_block = encode_block([input_block[0], input_block[1], input_block[2]]);
+            consumed += BASE64_INPUT_BLOCK_SIZE;
+            self.output_blocks += 1;
+        }
+        if !self.is_output_full() {
+            // fewer than BASE64_INPUT_BLOCK_SIZE bytes remain
+            for &byte in input_blocks.remainder() {
+                self.input_buffer[self.input_position] = byte;
+                self.input_position += 1;
+                consumed += 1;
+            }
+        }
+        consumed
+    }
+
+    /// encode the carried partial block into the free block at the end of the output buffer & return the encoded length
+    fn encode_partial_block(&mut self, padding: bool) -> usize {
+        let partial_block = self.input_buffer.split_at(self.input_position).0;
+        let output_block = &mut self.output_buffer[self.output_blocks];
+        if padding {
+            BASE64_URL_SAFE.encode_slice(partial_block, output_block).unwrap()
+        } else {
+            BASE64_URL_SAFE_NO_PAD.encode_slice(partial_block, output_block).unwrap()
+        }
+    }
+}
+
+impl<T: Write, const MAX_BLOCKS: usize> Base64UrlBlockEncoder<T, MAX_BLOCKS> {
+
+    fn write_pending_output(&mut self) -> Result<(), <Self as ErrorType>::Error> {
+        self.inner.write_all(self.output_buffer.split_at(self.output_blocks).0.as_flattened())?;
+        self.output_written();
+        Ok(())
+    }
+
+    /// encode any trailing partial block, optionally padding it, write all remaining output & return the total number of bytes written to the inner writer
     pub fn finalize(mut self, padding: bool) -> Result<usize, <Self as ErrorType>::Error> {
         if self.input_position > 0 {
-            self.encode_final_block(padding)?;
+            if self.is_output_full() {
+                self.write_pending_output()?;
+            }
+            let encoded_len = self.encode_partial_block(padding);
+            let output_len = self.output_blocks * BASE64_OUTPUT_BLOCK_SIZE + encoded_len;
+            self.inner.write_all(&self.output_buffer.as_flattened()[..output_len])?;
+            self.total_bytes_out += output_len;
+        } else {
+            self.write_pending_output()?;
         }
         Ok(self.total_bytes_out)
     }
+}
+
+impl<T: ErrorType, const MAX_BLOCKS: usize> ErrorType for Base64UrlBlockEncoder<T, MAX_BLOCKS> {
+    type Error = T::Error;
+}
 
-    fn is_block_ready(&mut self, byte: u8) -> bool {
-        self.input_buffer[self.input_position] = byte;
-        self.input_position += 1;
-        self.input_position == self.input_buffer.len()
+impl<T: Write, const MAX_BLOCKS: usize> Write for Base64UrlBlockEncoder<T, MAX_BLOCKS> {
+
+    fn write(&mut self, data: &[u8]) -> Result<usize, <Self as ErrorType>::Error> {
+        // a full output buffer is only written out before accepting more input, so nothing is consumed if the inner writer fails
+        if self.is_output_full() {
+            self.write_pending_output()?;
+        }
+        Ok(self.encode_blocks(data))
     }
 
-    fn encode_final_block(&mut self, padding: bool) -> Result<(), <Self as ErrorType>::Error> {
-        let partial_block = self.input_buffer.split_at(self.input_position).0;
-        let encoded_len = if padding {
-            BASE64_URL_SAFE.encode_slice(partial_block, &mut self.output_buffer).unwrap()
-            
+    /// write the buffered whole blocks to the inner writer & flush it. a trailing partial block is only written by finalize
+    fn flush(&mut self) -> Result<(), <Self as ErrorType>::Error> {
+        self.write_pending_output()?;
+        self.inner.flush()
+    }
+
+}
+
+#[cfg(feature = "async")]
+impl<T: embedded_io_async::Write, const MAX_BLOCKS: usize> Base64UrlBlockEncoder<T, MAX_BLOCKS> {
+
+    async fn write_pending_output_async(&mut self) -> Result<(), <Self as ErrorType>::Error> {
+        self.inner.write_all(self.output_buffer.split_at(self.output_blocks).0.as_flattened()).await?;
+        self.output_written();
+        Ok(())
+    }
+
+    /// encode any trailing partial block, optionally padding it, write all remaining output & return the total number of bytes written to the inner writer
+    pub async fn finalize_async(mut self, padding: bool) -> Result<usize, <Self as ErrorType>::Error> {
+        if self.input_position > 0 {
+            if self.is_output_full() {
+                self.write_pending_output_async().await?;
+            }
+            let encoded_len = self.encode_partial_block(padding);
+            let output_len = self.output_blocks * BASE64_OUTPUT_BLOCK_SIZE + encoded_len;
+            self.inner.write_all(&self.output_buffer.as_flattened()[..output_len]).await?;
+            self.total_bytes_out += output_len;
         } else {
-            BASE64_URL_SAFE_NO_PAD.encode_slice(partial_block, &mut self.output_buffer).unwrap()
+            self.write_pending_output_async().await?;
+        }
+        Ok(self.total_bytes_out)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T: embedded_io_async::Write, const MAX_BLOCKS: usize> embedded_io_async::Write for Base64UrlBlockEncoder<T, MAX_BLOCKS> {
+
+    async fn write(&mut self, data: &[u8]) -> Result<usize, <Self as ErrorType>::Error> {
+        // a full output buffer is only written out before accepting more input, so nothing is consumed if the inner writer fails
+        if self.is_output_full() {
+            self.write_pending_output_async().await?;
+        }
+        Ok(self.encode_blocks(data))
+    }
+
+    async fn flush(&mut self) -> Result<(), <Self as ErrorType>::Error> {
+        self.write_pending_output_async().await?;
+        self.inner.flush().await
+    }
+
+}
+
+/// marks bytes that are not in the base64url alphabet
+const INVALID_SYMBOL: u8 = 0xff;
+
+/// maps each byte to its 6 bit value in the base64url alphabet
+const BASE64_URL_DECODE_TABLE: [u8; 256] = {
+    let mut table = [INVALID_SYMBOL; 256];
+    let mut i = 0;
+    while i < BASE64_URL_ALPHABET.len() {
+        table[BASE64_URL_ALPHABET[i] as usize] = i as u8;
+        i += 1;
+    }
+    table
+};
+
+/// a failure to decode base64url with a Base64UrlBlockDecoder
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Base64DecodeFailure<E> {
+    /// the input is not canonical unpadded base64url. offsets are from the start of all input written to the decoder
+    Decode(DecodeError),
+    /// the inner writer failed
+    Write(E),
+}
+
+impl<E: core::fmt::Debug> core::fmt::Display for Base64DecodeFailure<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> Result<(), core::fmt::Error> {
+        match self {
+            Self::Decode(e) => write!(f, "invalid base64url: {}", e),
+            Self::Write(e) => write!(f, "the output could not be written to: {:?}", e),
+        }
+    }
+}
+
+impl<E: core::fmt::Debug> core::error::Error for Base64DecodeFailure<E> {}
+
+#[cfg(feature = "defmt")]
+impl<E: defmt::Format> defmt::Format for Base64DecodeFailure<E> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            Self::Decode(e) => defmt::write!(f, "invalid base64url: {}", defmt::Display2Format(e)),
+            Self::Write(e) => defmt::write!(f, "the output could not be written to: {}", e),
+        }
+    }
+}
+
+impl<E: embedded_io::Error> embedded_io::Error for Base64DecodeFailure<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Decode(_) => ErrorKind::InvalidData,
+            Self::Write(e) => e.kind(),
+        }
+    }
+}
+
+/// a writer that decodes unpadded base64url text into an inner writer.
+/// decoded blocks are buffered & only written to the inner writer by the first write after MAX_BLOCKS blocks are ready, when flushed or when finalized.
+/// the trailing partial block is only decoded by finalize, which also rejects input that is not canonical
+pub struct Base64UrlBlockDecoder<T, const MAX_BLOCKS: usize = DEFAULT_MAX_BLOCKS> {
+    inner: T,
+    input_buffer: [u8; BASE64_OUTPUT_BLOCK_SIZE],
+    output_buffer: [[u8; BASE64_INPUT_BLOCK_SIZE]; MAX_BLOCKS],
+    input_position: usize,
+    output_blocks: usize,
+    total_bytes_in: usize,
+    total_bytes_out: usize,
+}
+
+impl<T, const MAX_BLOCKS: usize> Base64UrlBlockDecoder<T, MAX_BLOCKS> {
+
+    /// the number of blocks buffered before they are written to the inner writer
+    pub const MAX_BLOCKS: usize = MAX_BLOCKS;
+
+    /// the size of the output buffer, which is the largest write made to the inner writer
+    pub const MAX_OUTPUT_BYTES: usize = MAX_BLOCKS * BASE64_INPUT_BLOCK_SIZE;
+
+    const AT_LEAST_ONE_BLOCK: () = assert!(MAX_BLOCKS > 0, "a Base64UrlBlockDecoder must buffer at least one block");
+
+    /// construct a decoder that writes to the provided inner writer
+    pub fn new(inner: T) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::AT_LEAST_ONE_BLOCK;
+        Self {
+            inner,
+            input_buffer: [0_u8; BASE64_OUTPUT_BLOCK_SIZE],
+            output_buffer: [[0_u8; BASE64_INPUT_BLOCK_SIZE]; MAX_BLOCKS],
+            input_position: 0_usize,
+            output_blocks: 0_usize,
+            total_bytes_in: 0,
+            total_bytes_out: 0,
+        }
+    }
+
+    fn is_output_full(&self) -> bool {
+        self.output_blocks == MAX_BLOCKS
+    }
+
+    fn output_written(&mut self) {
+        self.total_bytes_out += self.output_blocks * BASE64_INPUT_BLOCK_SIZE;
+        self.output_blocks = 0;
+    }
+
+    /// decode symbols until data runs out or the output buffer is full & return the number of bytes of data consumed
+    fn decode_blocks(&mut self, data: &[u8]) -> Result<usize, DecodeError> {
+        let mut consumed = 0;
+        for &byte in data {
+            if self.is_output_full() {
+                break;
+            }
+            let symbol = BASE64_URL_DECODE_TABLE[byte as usize];
+            if symbol == INVALID_SYMBOL {
+                return Err(DecodeError::InvalidByte(self.total_bytes_in, byte));
+            }
+            self.input_buffer[self.input_position] = symbol;
+            self.input_position += 1;
+            self.total_bytes_in += 1;
+            consumed += 1;
+            if self.input_position == BASE64_OUTPUT_BLOCK_SIZE {
+                let [a, b, c, d] = self.input_buffer;
+                self.output_buffer[self.output_blocks] = [a << 2 | b >> 4, b << 4 | c >> 2, c << 6 | d];
+                self.output_blocks += 1;
+                self.input_position = 0;
+            }
+        }
+        Ok(consumed)
+    }
+
+    /// decode the carried partial block into the free block at the end of the output buffer & return the decoded length
+    fn decode_partial_block(&mut self) -> Result<usize, DecodeError> {
+        let [a, b, c, _] = self.input_buffer;
+        let (decoded, decoded_len, trailing_bits) = match self.input_position {
+            0 => return Ok(0),
+            1 => return Err(DecodeError::InvalidLength(self.total_bytes_in)),
+            2 => ([a << 2 | b >> 4, 0, 0], 1, b & 0x0f),
+            _ => ([a << 2 | b >> 4, b << 4 | c >> 2, 0], 2, c & 0x03),
         };
-        let to_write = self.output_buffer.split_at(encoded_len).0;
-        // eprintln!("flushing partial final block data: {}", core::str::from_utf8(to_write).unwrap_or("not utf8 oopsie"));
-        self.inner.write_all(to_write)?;
-        self.total_bytes_out += to_write.len();
-        Ok(())
+        if trailing_bits != 0 {
+            let last_symbol = self.input_buffer[self.input_position - 1];
+            return Err(DecodeError::InvalidLastSymbol(self.total_bytes_in - 1, BASE64_URL_ALPHABET[last_symbol as usize]));
+        }
+        self.output_buffer[self.output_blocks] = decoded;
+        Ok(decoded_len)
     }
+}
+
+impl<T: Write, const MAX_BLOCKS: usize> Base64UrlBlockDecoder<T, MAX_BLOCKS> {
 
-    fn encode_full_block(&mut self) -> Result<(), <Self as ErrorType>::Error> {
-        assert_eq!(BASE64_OUTPUT_BLOCK_SIZE,BASE64_URL_SAFE_NO_PAD.encode_slice(self.input_buffer, &mut self.output_buffer).unwrap());
-        self.input_position = 0;
-        self.inner.write_all(&self.output_buffer)?;
-        self.total_bytes_out += self.output_buffer.len();
+    fn write_pending_output(&mut self) -> Result<(), T::Error> {
+        self.inner.write_all(self.output_buffer.split_at(self.output_blocks).0.as_flattened())?;
+        self.output_written();
         Ok(())
     }
+
+    /// decode any trailing partial block, write all remaining output & return the total number of bytes written to the inner writer
+    pub fn finalize(mut self) -> Result<usize, <Self as ErrorType>::Error> {
+        if self.is_output_full() {
+            self.write_pending_output().map_err(Base64DecodeFailure::Write)?;
+        }
+        let decoded_len = self.decode_partial_block().map_err(Base64DecodeFailure::Decode)?;
+        let output_len = self.output_blocks * BASE64_INPUT_BLOCK_SIZE + decoded_len;
+        self.inner.write_all(&self.output_buffer.as_flattened()[..output_len]).map_err(Base64DecodeFailure::Write)?;
+        self.total_bytes_out += output_len;
+        Ok(self.total_bytes_out)
+    }
 }
 
-impl<T: ErrorType> ErrorType for Base64UrlBlockEncoder<T> {
-    type Error = T::Error;
+impl<T: ErrorType, const MAX_BLOCKS: usize> ErrorType for Base64UrlBlockDecoder<T, MAX_BLOCKS> {
+    type Error = Base64DecodeFailure<T::Error>;
 }
 
-impl<T: Write> Write for Base64UrlBlockEncoder<T> {
+impl<T: Write, const MAX_BLOCKS: usize> Write for Base64UrlBlockDecoder<T, MAX_BLOCKS> {
 
-    // TODO: optimize this
-    fn write(&mut self, mut data: &[u8]) -> Result<usize, <Self as ErrorType>::Error> {
-        let mut ret = 0;
-        while let Some((first, remaining)) = data.split_first() {
-            ret +=1;
-            if self.is_block_ready(*first) {
-                self.encode_full_block()?;
-                return Ok(ret);
-            }
-            data = remaining;
+    fn write(&mut self, data: &[u8]) -> Result<usize, <Self as ErrorType>::Error> {
+        // a full output buffer is only written out before accepting more input, so nothing is consumed if the inner writer fails
+        if self.is_output_full() {
+            self.write_pending_output().map_err(Base64DecodeFailure::Write)?;
         }
-        Ok(ret)
+        self.decode_blocks(data).map_err(Base64DecodeFailure::Decode)
     }
 
+    /// write the buffered whole blocks to the inner writer & flush it. a trailing partial block is only written by finalize
     fn flush(&mut self) -> Result<(), <Self as ErrorType>::Error> {
-        self.inner.flush()
+        self.write_pending_output().map_err(Base64DecodeFailure::Write)?;
+        self.inner.flush().map_err(Base64DecodeFailure::Write)
     }
 
 }
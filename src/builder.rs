@@ -0,0 +1,158 @@
+use embedded_io::Write;
+use lil_json::{JsonObject, JsonValue};
+
+use crate::{validation::to_numeric_date, Clock, JsonWebToken, JwtType};
+
+/// a failure to sign a JsonWebToken built by a JwtBuilder
+#[derive(Debug)]
+pub enum JwtBuildFailure<E> {
+    /// more claims were set than the builder can hold
+    TooManyClaims,
+    /// the Clock passed to expires_in could not provide the current time
+    ClockUnavailable,
+    /// the output could not be written to
+    Write(E),
+}
+
+impl<E: core::fmt::Debug> core::fmt::Display for JwtBuildFailure<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> Result<(), core::fmt::Error> {
+        match self {
+            Self::TooManyClaims => f.write_str("more claims were set than the builder can hold"),
+            Self::ClockUnavailable => f.write_str("the current time is unavailable"),
+            Self::Write(e) => write!(f, "the output could not be written to: {:?}", e),
+        }
+    }
+}
+
+impl<E: core::fmt::Debug> core::error::Error for JwtBuildFailure<E> {}
+
+#[cfg(feature = "defmt")]
+impl<E: defmt::Format> defmt::Format for JwtBuildFailure<E> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            Self::TooManyClaims => defmt::write!(f, "more claims were set than the builder can hold"),
+            Self::ClockUnavailable => defmt::write!(f, "the current time is unavailable"),
+            Self::Write(e) => defmt::write!(f, "the output could not be written to: {}", e),
+        }
+    }
+}
+
+/// a failure recorded by a setter & reported when signing
+#[derive(Debug,Clone,Copy)]
+enum DeferredFailure {
+    TooManyClaims,
+    ClockUnavailable,
+}
+
+/// a fluent builder for a JsonWebToken with up to MAX_CLAIMS claims
+#[derive(Debug)]
+pub struct JwtBuilder<'a, const MAX_CLAIMS: usize> {
+    algorithm: JwtType,
+    claims: JsonObject<'a,MAX_CLAIMS>,
+    typ: Option<&'a str>,
+    kid: Option<&'a str>,
+    failure: Option<DeferredFailure>,
+}
+
+impl<'a, const MAX_CLAIMS: usize> JwtBuilder<'a, MAX_CLAIMS> {
+
+    /// construct a JwtBuilder for a JsonWebToken that will be signed with the specified algorithm
+    pub const fn new(algorithm: JwtType) -> Self {
+        Self {
+            algorithm,
+            claims: JsonObject::new(),
+            typ: None,
+            kid: None,
+            failure: None,
+        }
+    }
+
+    /// set a claim, replacing any existing claim with the same name
+    pub fn claim(mut self, name: &'a str, value: JsonValue<'a>) -> Self {
+        if let Some(existing) = self.claims.as_mut_slice().iter_mut().find(|claim| claim.key == name) {
+            existing.value = value;
+        } else if self.claims.push_field(name, value).is_err() {
+            self.failure.get_or_insert(DeferredFailure::TooManyClaims);
+        }
+        self
+    }
+
+    fn numeric_date_claim(self, name: &'a str, seconds: u64) -> Self {
+        self.claim(name, JsonValue::Number(to_numeric_date(seconds)))
+    }
+
+    /// set the `iss` claim
+    pub fn issuer(self, iss: &'a str) -> Self {
+        self.claim("iss", JsonValue::String(iss))
+    }
+
+    /// set the `sub` claim
+    pub fn subject(self, sub: &'a str) -> Self {
+        self.claim("sub", JsonValue::String(sub))
+    }
+
+    /// set the `aud` claim
+    pub fn audience(self, aud: &'a str) -> Self {
+        self.claim("aud", JsonValue::String(aud))
+    }
+
+    /// set the `exp` claim (seconds since the unix epoch)
+    pub fn expires_at(self, exp: u64) -> Self {
+        self.numeric_date_claim("exp", exp)
+    }
+
+    /// set the `exp` claim to the provided number of seconds after the time reported by the provided Clock
+    pub fn expires_in(mut self, seconds: u64, clock: &dyn Clock) -> Self {
+        match clock.now() {
+            Some(now) => self.expires_at(now.saturating_add(seconds)),
+            None => {
+                self.failure.get_or_insert(DeferredFailure::ClockUnavailable);
+                self
+            },
+        }
+    }
+
+    /// set the `nbf` claim (seconds since the unix epoch)
+    pub fn not_before(self, nbf: u64) -> Self {
+        self.numeric_date_claim("nbf", nbf)
+    }
+
+    /// set the `iat` claim (seconds since the unix epoch)
+    pub fn issued_at(self, iat: u64) -> Self {
+        self.numeric_date_claim("iat", iat)
+    }
+
+    /// set the `jti` claim
+    pub fn jwt_id(self, jti: &'a str) -> Self {
+        self.claim("jti", JsonValue::String(jti))
+    }
+
+    /// set the `typ` header
+    pub fn typ(mut self, typ: &'a str) -> Self {
+        self.typ = Some(typ);
+        self
+    }
+
+    /// set the `kid` header
+    pub fn key_id(mut self, kid: &'a str) -> Self {
+        self.kid = Some(kid);
+        self
+    }
+
+    /// sign the built JsonWebToken with the provided key into the provided output & return the number of bytes written
+    pub fn sign_into<T: Write>(&self, output: T, key: &[u8]) -> Result<usize,JwtBuildFailure<T::Error>> {
+        match self.failure {
+            None => {},
+            Some(DeferredFailure::TooManyClaims) => return Err(JwtBuildFailure::TooManyClaims),
+            Some(DeferredFailure::ClockUnavailable) => return Err(JwtBuildFailure::ClockUnavailable),
+        }
+        let mut token = JsonWebToken::from_claims(self.claims.as_slice());
+        if let Some(typ) = self.typ {
+            token = token.with_typ(typ);
+        }
+        if let Some(kid) = self.kid {
+            token = token.with_kid(kid);
+        }
+        token.serialize(output, self.algorithm, key).map_err(JwtBuildFailure::Write)
+    }
+}
@@ -0,0 +1,58 @@
+use core::cell::Cell;
+
+/// a source of the current time that is consulted when validating time claims
+pub trait Clock {
+    /// the current time in seconds since the unix epoch, or None if the time is not known (e.g. an RTC that has not been set yet)
+    fn now(&self) -> Option<u64>;
+}
+
+impl<F: Fn() -> Option<u64>> Clock for F {
+    fn now(&self) -> Option<u64> {
+        self()
+    }
+}
+
+/// a Clock that always reports the time it was last set to, useful for tests & for callers that read the time themselves
+#[derive(Debug,Clone,Default)]
+pub struct FixedClock {
+    now: Cell<u64>,
+}
+
+impl FixedClock {
+
+    /// construct a FixedClock that reports the provided time
+    pub const fn new(now: u64) -> Self {
+        Self { now: Cell::new(now) }
+    }
+
+    /// change the time reported by this FixedClock
+    pub fn set(&self, now: u64) {
+        self.now.set(now);
+    }
+
+    /// move the time reported by this FixedClock forward by the provided number of seconds
+    pub fn advance(&self, seconds: u64) {
+        self.now.set(self.now.get().saturating_add(seconds));
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> Option<u64> {
+        Some(self.now.get())
+    }
+}
+
+/// a Clock backed by `std::time::SystemTime`
+#[cfg(feature = "std")]
+#[derive(Debug,Clone,Copy,Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now(&self) -> Option<u64> {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()
+            .map(|duration| duration.as_secs())
+    }
+}
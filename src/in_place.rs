@@ -0,0 +1,58 @@
+use core::ops::Range;
+
+use base64::{prelude::BASE64_URL_SAFE_NO_PAD, DecodeError, DecodeSliceError, Engine};
+use lil_json::JsonField;
+
+use crate::{base64_writer::{BASE64_INPUT_BLOCK_SIZE, BASE64_OUTPUT_BLOCK_SIZE}, parse_claims, parse_jose_header, split_jwt_parts, verify_signature, JwtParseFailure, JwtSegment, JwtType, Validation};
+
+/// deserialize a JWT by decoding its header & claims in place within data, so that the claims borrow from data & no base64buffer is needed.
+/// data is overwritten once the signature has been verified, even if the header or claims turn out to be invalid
+pub fn deserialize_jwt_in_place<'a>(data: &'a mut [u8], claims_buffer: &mut [JsonField<'a,'a>], algorithm: &JwtType, secret: &[u8], validation: &Validation<'_>) -> Result<usize,JwtParseFailure> {
+    let (header_len, body_len) = {
+        let (header_b64,body_b64,signature_b64) = split_jwt_parts(data)?;
+        verify_signature(header_b64, body_b64, signature_b64, algorithm, secret)?;
+        (header_b64.len(), body_b64.len())
+    };
+    let body_start = header_len + 1;
+    let header_decoded_len = decode_in_place(data, JwtSegment::Header, 0, 0..header_len)?;
+    let body_decoded_len = decode_in_place(data, JwtSegment::Payload, header_decoded_len, body_start..body_start + body_len)?;
+    let (decoded_header, remaining_data) = data.split_at_mut(header_decoded_len);
+    parse_jose_header(decoded_header, algorithm, validation)?;
+    let (num_claims,audience_array) = parse_claims(remaining_data.split_at_mut(body_decoded_len).0, claims_buffer)?;
+    validation.validate_claims(claims_buffer.split_at(num_claims).0, audience_array)?;
+    Ok(num_claims)
+}
+
+/// decode the unpadded base64url in data[input] into data starting at output_start & return the decoded length.
+/// output_start must not be after input.start; since decoding shrinks data the output never overtakes the input
+fn decode_in_place(data: &mut [u8], segment: JwtSegment, output_start: usize, input: Range<usize>) -> Result<usize,JwtParseFailure> {
+    let mut decoded_len = 0;
+    let mut read_position = input.start;
+    while read_position < input.end {
+        let block_end = input.end.min(read_position + BASE64_OUTPUT_BLOCK_SIZE);
+        let mut encoded_block = [0_u8; BASE64_OUTPUT_BLOCK_SIZE];
+        let encoded_block = encoded_block.split_at_mut(block_end - read_position).0;
+        encoded_block.copy_from_slice(&data[read_position..block_end]);
+        let mut decoded_block = [0_u8; BASE64_INPUT_BLOCK_SIZE];
+        let n = match BASE64_URL_SAFE_NO_PAD.decode_slice(&*encoded_block, &mut decoded_block) {
+            Ok(n) => n,
+            Err(DecodeSliceError::OutputSliceTooSmall) => unreachable!("a base64 block decodes to at most 3 bytes"),
+            Err(DecodeSliceError::DecodeError(e)) => return Err(JwtParseFailure::InvalidBase64Url(segment, offset_decode_error(e, read_position - input.start, input.len()))),
+        };
+        let output_position = output_start + decoded_len;
+        data[output_position..output_position + n].copy_from_slice(decoded_block.split_at(n).0);
+        decoded_len += n;
+        read_position = block_end;
+    }
+    Ok(decoded_len)
+}
+
+/// make the position of a decode error in a single block relative to the whole segment
+pub(crate) fn offset_decode_error(e: DecodeError, block_offset: usize, segment_len: usize) -> DecodeError {
+    match e {
+        DecodeError::InvalidByte(i, b) => DecodeError::InvalidByte(block_offset + i, b),
+        DecodeError::InvalidLastSymbol(i, b) => DecodeError::InvalidLastSymbol(block_offset + i, b),
+        DecodeError::InvalidLength(_) => DecodeError::InvalidLength(segment_len),
+        DecodeError::InvalidPadding => DecodeError::InvalidPadding,
+    }
+}
@@ -0,0 +1,75 @@
+use core::convert::Infallible;
+
+use embedded_io::{ErrorType, Write};
+
+/// the length of the unpadded base64url encoding of n bytes
+pub const fn base64url_len(n: usize) -> usize {
+    (n / 3) * 4 + match n % 3 {
+        0 => 0,
+        1 => 2,
+        _ => 3,
+    }
+}
+
+/// the length of the data encoded by n bytes of unpadded base64url, assuming they are valid base64url
+pub const fn base64url_decoded_len(n: usize) -> usize {
+    (n / 4) * 3 + match n % 4 {
+        2 => 1,
+        3 => 2,
+        _ => 0,
+    }
+}
+
+/// an upper bound on the base64buffer length needed to deserialize any JWT of up to max_token_len bytes.
+/// this can be used to size buffers at compile time
+pub const fn max_base64buffer_len(max_token_len: usize) -> usize {
+    base64url_decoded_len(max_token_len.saturating_sub(2))
+}
+
+/// the length of a JSON string as serialized by lil-json, which escapes quotes
+const fn json_string_len(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let mut ret = bytes.len() + 2;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'"' {
+            ret += 1;
+        }
+        i += 1;
+    }
+    ret
+}
+
+/// the length of a `"key":"value"` JSON string field
+const fn json_string_field_len(key: &str, value: &str) -> usize {
+    json_string_len(key) + 1 + json_string_len(value)
+}
+
+/// the length of a serialized JOSE header with the provided parameters
+pub(crate) const fn jose_header_json_len(alg: &str, typ: Option<&str>, kid: Option<&str>) -> usize {
+    let mut ret = 2 + json_string_field_len("alg", alg);
+    if let Some(typ) = typ {
+        ret += 1 + json_string_field_len("typ", typ);
+    }
+    if let Some(kid) = kid {
+        ret += 1 + json_string_field_len("kid", kid);
+    }
+    ret
+}
+
+/// the length of a compact JWT given the lengths of its serialized header, serialized claims & signature
+pub(crate) const fn compact_len(header_json_len: usize, claims_json_len: usize, signature_len: usize) -> usize {
+    base64url_len(header_json_len) + 1 + base64url_len(claims_json_len) + 1 + base64url_len(signature_len)
+}
+
+/// a writer that discards its input, used to measure serialized lengths
+pub(crate) struct Empty{}
+
+impl ErrorType for Empty {
+    type Error = Infallible;
+}
+
+impl Write for Empty {
+    fn write(& mut self, data: &[u8]) -> Result<usize, <Self as ErrorType>::Error> { Ok(data.len()) }
+    fn flush(&mut self) -> Result<(), Self::Error> { Ok(()) }
+}
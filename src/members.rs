@@ -0,0 +1,207 @@
+use lil_json::{JsonField, JsonParseFailure, JsonValue};
+
+use crate::validation::ClaimValue;
+use crate::JwtParseFailure;
+
+/// the top-level members of a JSON object as (key, raw value) pairs, read without modifying or copying the object.
+/// keys are returned without their quotes & escapes are not decoded, matching lil-json.
+/// nested objects & arrays are skipped rather than rejected, and are only checked for balanced brackets
+#[derive(Clone)]
+pub(crate) struct Members<'a> {
+    data: &'a [u8],
+    index: usize,
+    first: bool,
+}
+
+impl<'a> Members<'a> {
+
+    /// check that data starts with a well formed JSON object & iterate over its members
+    pub(crate) fn new(data: &'a [u8]) -> Result<Self,JsonParseFailure> {
+        let start = skip_whitespace(data, 0);
+        if data.get(start) != Some(&b'{') {
+            return Err(JsonParseFailure::InvalidStructure);
+        }
+        let members = Self { data, index: start + 1, first: true };
+        let mut check = members.clone();
+        while check.next_member()?.is_some() {}
+        Ok(members)
+    }
+
+    /// the next member, or None at the closing brace
+    fn next_member(&mut self) -> Result<Option<(&'a str,&'a [u8])>,JsonParseFailure> {
+        let data = self.data;
+        let mut index = skip_whitespace(data, self.index);
+        match data.get(index) {
+            None => return Err(JsonParseFailure::Incomplete),
+            Some(b'}') => {
+                self.index = index;
+                return Ok(None);
+            },
+            Some(b',') if !self.first => index = skip_whitespace(data, index + 1),
+            Some(_) if self.first => {},
+            Some(_) => return Err(JsonParseFailure::InvalidStructure),
+        }
+        let (key, key_end) = skip_string(data, index)?;
+        let key = core::str::from_utf8(key).map_err(|_| JsonParseFailure::InvalidStringField)?;
+        index = skip_whitespace(data, key_end);
+        if data.get(index) != Some(&b':') {
+            return Err(JsonParseFailure::InvalidStructure);
+        }
+        let value_start = skip_whitespace(data, index + 1);
+        let value_end = skip_value(data, value_start)?;
+        self.index = value_end;
+        self.first = false;
+        Ok(Some((key, &data[value_start..value_end])))
+    }
+}
+
+impl<'a> Iterator for Members<'a> {
+    type Item = (&'a str,&'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // the object was checked by Members::new
+        self.next_member().ok().flatten()
+    }
+}
+
+/// whether data is a JSON object with an `aud` member in the array form
+pub(crate) fn has_audience_array(data: &[u8]) -> bool {
+    Members::new(data).is_ok_and(|mut members| members.any(|(key, value)| key == "aud" && matches!(claim_value(value), ClaimValue::Array(_))))
+}
+
+/// parse the members of a JSON object into claims_buffer, leaving out its `aud` member in the array form.
+/// the other members are read the way lil-json reads them, so the `aud` array is the only thing accepted beyond what lil-json parses.
+/// returns the number of claims & the raw `aud` array
+pub(crate) fn parse_members_without_audience_array<'a>(data: &'a [u8], claims_buffer: &mut [JsonField<'a,'a>]) -> Result<(usize,Option<&'a [u8]>),JwtParseFailure> {
+    let mut num_claims = 0;
+    let mut audience_array = None;
+    for (key, raw) in Members::new(data).map_err(JwtParseFailure::InvalidClaims)? {
+        if key == "aud" && raw.first() == Some(&b'[') {
+            if audience_array.replace(raw).is_some() {
+                return Err(JwtParseFailure::DuplicateClaim);
+            }
+            continue;
+        }
+        let claim = claims_buffer.get_mut(num_claims).ok_or(JwtParseFailure::InvalidClaims(JsonParseFailure::TooManyFields))?;
+        let key = ascii_str(key.as_bytes()).map_err(JwtParseFailure::InvalidClaims)?;
+        *claim = JsonField::new(key, json_value(raw).map_err(JwtParseFailure::InvalidClaims)?);
+        num_claims += 1;
+    }
+    Ok((num_claims,audience_array))
+}
+
+/// read a raw value the way lil-json does: strings keep their escapes, numbers must be integers & nested values are rejected
+fn json_value(raw: &[u8]) -> Result<JsonValue<'_>,JsonParseFailure> {
+    match raw {
+        b"true" => Ok(JsonValue::Boolean(true)),
+        b"false" => Ok(JsonValue::Boolean(false)),
+        [b'"', string @ .., b'"'] => ascii_str(string).map(JsonValue::String),
+        [b'0'..=b'9' | b'-', ..] => core::str::from_utf8(raw).ok().and_then(|n| n.parse().ok()).map(JsonValue::Number).ok_or(JsonParseFailure::InvalidNumericField),
+        _ => Err(JsonParseFailure::InvalidStructure),
+    }
+}
+
+/// lil-json only accepts ASCII strings
+fn ascii_str(bytes: &[u8]) -> Result<&str,JsonParseFailure> {
+    core::str::from_utf8(bytes).ok().filter(|s| s.is_ascii()).ok_or(JsonParseFailure::InvalidStringField)
+}
+
+/// interpret the raw value of a member for the claim checks
+pub(crate) fn claim_value(raw: &[u8]) -> ClaimValue<'_> {
+    match raw.first() {
+        Some(b'"') => core::str::from_utf8(&raw[1..raw.len() - 1]).map_or(ClaimValue::Other, ClaimValue::String),
+        Some(b'[') => ClaimValue::Array(raw),
+        _ => core::str::from_utf8(raw).ok().and_then(|n| n.parse().ok()).map_or(ClaimValue::Other, ClaimValue::Number),
+    }
+}
+
+/// whether the raw JSON array contains the expected string. an array with an element that isn't a string never does
+pub(crate) fn string_array_contains(array: &[u8], expected: &str) -> bool {
+    let mut found = false;
+    let mut first = true;
+    let mut index = 1;
+    loop {
+        index = skip_whitespace(array, index);
+        match array.get(index) {
+            Some(b']') => return found,
+            Some(b',') if !first => index = skip_whitespace(array, index + 1),
+            Some(_) if first => {},
+            _ => return false,
+        }
+        match skip_string(array, index) {
+            Ok((element, end)) => {
+                found |= element == expected.as_bytes();
+                first = false;
+                index = end;
+            },
+            Err(_) => return false,
+        }
+    }
+}
+
+/// the index of the first non-whitespace byte at or after index
+fn skip_whitespace(data: &[u8], mut index: usize) -> usize {
+    while data.get(index).is_some_and(u8::is_ascii_whitespace) {
+        index += 1;
+    }
+    index
+}
+
+/// the contents of the quoted string starting at index & the index just past its closing quote
+fn skip_string(data: &[u8], start: usize) -> Result<(&[u8],usize),JsonParseFailure> {
+    if data.get(start) != Some(&b'"') {
+        return Err(JsonParseFailure::InvalidStructure);
+    }
+    let mut index = start + 1;
+    while let Some(&byte) = data.get(index) {
+        match byte {
+            b'\\' => index += 2,
+            b'"' => return Ok((&data[start + 1..index], index + 1)),
+            _ => index += 1,
+        }
+    }
+    Err(JsonParseFailure::Incomplete)
+}
+
+/// the index just past the value starting at index
+fn skip_value(data: &[u8], start: usize) -> Result<usize,JsonParseFailure> {
+    match data.get(start) {
+        None => Err(JsonParseFailure::Incomplete),
+        Some(b'"') => skip_string(data, start).map(|(_, end)| end),
+        Some(b'{' | b'[') => skip_nested(data, start),
+        Some(_) => {
+            let mut index = start;
+            while data.get(index).is_some_and(|&b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'+' | b'.')) {
+                index += 1;
+            }
+            if index == start {
+                return Err(JsonParseFailure::InvalidStructure);
+            }
+            Ok(index)
+        },
+    }
+}
+
+/// the index just past the object or array starting at index
+fn skip_nested(data: &[u8], start: usize) -> Result<usize,JsonParseFailure> {
+    let mut depth = 0_usize;
+    let mut index = start;
+    while let Some(&byte) = data.get(index) {
+        match byte {
+            b'"' => {
+                index = skip_string(data, index)?.1;
+                continue;
+            },
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(index + 1);
+                }
+            },
+            _ => {},
+        }
+        index += 1;
+    }
+    Err(JsonParseFailure::Incomplete)
+}
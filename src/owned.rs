@@ -0,0 +1,112 @@
+use alloc::{string::String, vec, vec::Vec};
+
+use lil_json::{parse_json_object, JsonField, JsonValue, EMPTY_FIELD};
+
+use crate::{decode_jwt_payload, parse_claims, JsonWebToken, JwtParseFailure, JwtType, Validation, MAX_HEADER_FIELDS};
+
+/// an owned primitive JSON value
+#[derive(Debug,PartialEq,Eq,Clone)]
+pub enum OwnedJsonValue {
+    String(String),
+    Boolean(bool),
+    Number(i64),
+}
+
+impl OwnedJsonValue {
+    /// borrow this value as a lil-json value
+    pub fn as_json_value(&self) -> JsonValue<'_> {
+        match self {
+            Self::String(s) => JsonValue::String(s),
+            Self::Boolean(b) => JsonValue::Boolean(*b),
+            Self::Number(n) => JsonValue::Number(*n),
+        }
+    }
+}
+
+impl From<JsonValue<'_>> for OwnedJsonValue {
+    fn from(value: JsonValue<'_>) -> Self {
+        match value {
+            JsonValue::String(s) => Self::String(s.into()),
+            JsonValue::Boolean(b) => Self::Boolean(b),
+            JsonValue::Number(n) => Self::Number(n),
+        }
+    }
+}
+
+/// an owned field within a JSON object
+#[derive(Debug,PartialEq,Eq,Clone)]
+pub struct OwnedJsonField {
+    pub key: String,
+    pub value: OwnedJsonValue,
+}
+
+impl OwnedJsonField {
+    /// borrow this field as a lil-json field
+    pub fn as_json_field(&self) -> JsonField<'_,'_> {
+        JsonField::new(&self.key, self.value.as_json_value())
+    }
+}
+
+impl From<JsonField<'_,'_>> for OwnedJsonField {
+    fn from(field: JsonField<'_,'_>) -> Self {
+        Self { key: field.key.into(), value: field.value.into() }
+    }
+}
+
+/// a verified JSON Web Token whose header & claims are owned, so it can outlive the data it was decoded from
+#[derive(Debug,PartialEq,Eq,Clone)]
+pub struct DecodedJwt {
+    pub header: Vec<OwnedJsonField>,
+    pub claims: Vec<OwnedJsonField>,
+}
+
+impl DecodedJwt {
+
+    /// attempt to decode a JsonWebToken with the specified algorithm & secret from the provided data, applying the provided validation
+    pub fn decode(data: &[u8], algorithm: JwtType, secret: &[u8], validation: &Validation<'_>) -> Result<Self,JwtParseFailure> {
+        let mut base64buffer = vec![0_u8; data.len()];
+        let (decoded_header,decoded_claims) = decode_jwt_payload(data, &algorithm, secret, &mut base64buffer, validation)?;
+        let mut header_buffer = [EMPTY_FIELD; MAX_HEADER_FIELDS];
+        let num_header_fields = match parse_json_object(decoded_header, &mut header_buffer) {
+            Ok((_num_bytes,n)) => n,
+            Err(j) => return Err(JwtParseFailure::InvalidHeader(j)),
+        };
+        // every field has a colon, so this is an upper bound on the number of claims
+        let max_claims = decoded_claims.iter().filter(|&&b| b == b':').count();
+        let mut claims_buffer = vec![EMPTY_FIELD; max_claims];
+        let (num_claims,audience_array) = parse_claims(decoded_claims, &mut claims_buffer)?;
+        let claims = claims_buffer.split_at(num_claims).0;
+        validation.validate_claims(claims, audience_array)?;
+        Ok(Self {
+            header: header_buffer.split_at(num_header_fields).0.iter().copied().map(OwnedJsonField::from).collect(),
+            claims: claims.iter().copied().map(OwnedJsonField::from).collect(),
+        })
+    }
+
+    /// the value of the header parameter with the provided name, if present
+    pub fn header_value(&self, name: &str) -> Option<&OwnedJsonValue> {
+        self.header.iter().find(|field| field.key == name).map(|field| &field.value)
+    }
+
+    /// the value of the claim with the provided name, if present
+    pub fn claim(&self, name: &str) -> Option<&OwnedJsonValue> {
+        self.claims.iter().find(|field| field.key == name).map(|field| &field.value)
+    }
+}
+
+impl JsonWebToken<'_> {
+
+    /// serialize this JsonWebToken with the specified algorithm & secret into a new Vec
+    pub fn serialize_to_vec(&self, algorithm: JwtType, secret: &[u8]) -> Vec<u8> {
+        let mut ret = Vec::new();
+        match self.serialize(&mut ret, algorithm, secret) {
+            Ok(_num_bytes) => ret,
+            Err(infallible) => match infallible {},
+        }
+    }
+
+    /// serialize this JsonWebToken with the specified algorithm & secret into a new String
+    pub fn serialize_to_string(&self, algorithm: JwtType, secret: &[u8]) -> String {
+        String::from_utf8(self.serialize_to_vec(algorithm, secret)).expect("serialized JWTs are base64url & dots")
+    }
+}
@@ -0,0 +1,99 @@
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+
+use crate::JwtParseFailure;
+
+/// a record of previously accepted token ids (`jti` claims) that is consulted to reject replayed tokens
+pub trait ReplayGuard {
+    /// record a token id at time `now` that can be accepted until just before `expires` (its `exp` plus the validation leeway),
+    /// failing if the same token id was already recorded & has not expired yet
+    fn check_and_record(&self, jti: &str, expires: i64, now: i64) -> Result<(),JwtParseFailure>;
+}
+
+#[derive(Debug,Clone,Copy)]
+struct SeenToken {
+    hash: u64,
+    expires: i64,
+}
+
+/// the ring of a JtiCache
+struct SeenTokens<const N: usize> {
+    entries: [Option<SeenToken>; N],
+    next: usize,
+}
+
+/// a ReplayGuard that remembers up to N token ids in a fixed-size ring without allocating.
+/// token ids are stored as 64-bit FNV-1a hashes & are forgotten once their `exp` plus the validation leeway has passed.
+/// the ring is only accessed within a critical section, so a JtiCache can be a `static` shared between tasks & interrupts.
+/// this needs a critical-section implementation, which is provided by the `std` feature or by the HAL on embedded targets
+pub struct JtiCache<const N: usize> {
+    seen: Mutex<RefCell<SeenTokens<N>>>,
+}
+
+impl<const N: usize> core::fmt::Debug for JtiCache<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("JtiCache").field("capacity", &N).finish_non_exhaustive()
+    }
+}
+
+impl<const N: usize> Default for JtiCache<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> JtiCache<N> {
+
+    /// construct an empty JtiCache
+    pub const fn new() -> Self {
+        Self {
+            seen: Mutex::new(RefCell::new(SeenTokens { entries: [None; N], next: 0 })),
+        }
+    }
+
+    fn is_live(entry: Option<SeenToken>, now: i64) -> bool {
+        entry.is_some_and(|seen| seen.expires > now)
+    }
+}
+
+impl<const N: usize> ReplayGuard for JtiCache<N> {
+    fn check_and_record(&self, jti: &str, expires: i64, now: i64) -> Result<(),JwtParseFailure> {
+        let hash = fnv1a(jti.as_bytes());
+        critical_section::with(|cs| {
+            let mut seen_tokens = self.seen.borrow_ref_mut(cs);
+            let mut free_slot = None;
+            for (i, &seen) in seen_tokens.entries.iter().enumerate() {
+                if Self::is_live(seen, now) {
+                    if seen.is_some_and(|seen| seen.hash == hash) {
+                        return Err(JwtParseFailure::Replayed);
+                    }
+                } else if free_slot.is_none() {
+                    free_slot = Some(i);
+                }
+            }
+            // prefer the slot at the head of the ring so that entries are reused in order
+            let next = seen_tokens.next;
+            let slot = match (seen_tokens.entries.get(next), free_slot) {
+                (Some(&entry), _) if !Self::is_live(entry, now) => next,
+                (_, Some(free_slot)) => free_slot,
+                _ => return Err(JwtParseFailure::ReplayCacheFull),
+            };
+            seen_tokens.entries[slot] = Some(SeenToken { hash, expires });
+            seen_tokens.next = (slot + 1) % N;
+            Ok(())
+        })
+    }
+}
+
+/// 64-bit FNV-1a hash
+const fn fnv1a(data: &[u8]) -> u64 {
+    let mut hash = 0xcbf29ce484222325_u64;
+    let mut i = 0;
+    while i < data.len() {
+        hash ^= data[i] as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+        i += 1;
+    }
+    hash
+}
@@ -0,0 +1,69 @@
+use embedded_io::Write;
+use serde::{Deserialize, Serialize};
+
+use crate::members::{claim_value, Members};
+use crate::{decode_jwt_payload, default_typ, get_jose_header, serialize_jwt_with_header, serialize_slice_base64, JsonWebToken, JwtParseFailure, JwtPayload, JwtType, Validation};
+
+/// a JSON payload that has already been serialized
+struct RawJsonPayload<'a>(&'a [u8]);
+
+impl JwtPayload for RawJsonPayload<'_> {
+    fn serialize_base64<T: Write>(&self, output: T) -> Result<usize,T::Error> {
+        serialize_slice_base64(output, self.0)
+    }
+}
+
+/// a failure to serialize a JsonWebToken with a serde payload
+#[derive(Debug)]
+pub enum SerdeSerializeFailure<E> {
+    /// the payload could not be serialized into the provided JSON buffer
+    Json(serde_json_core::ser::Error),
+    /// the output could not be written to
+    Write(E),
+}
+
+impl<E: core::fmt::Debug> core::fmt::Display for SerdeSerializeFailure<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> Result<(), core::fmt::Error> {
+        match self {
+            Self::Json(e) => write!(f, "the payload could not be serialized: {}", e),
+            Self::Write(e) => write!(f, "the output could not be written to: {:?}", e),
+        }
+    }
+}
+
+impl<E: core::fmt::Debug> core::error::Error for SerdeSerializeFailure<E> {}
+
+#[cfg(feature = "defmt")]
+impl<E: defmt::Format> defmt::Format for SerdeSerializeFailure<E> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            Self::Json(e) => defmt::write!(f, "the payload could not be serialized: {}", defmt::Display2Format(e)),
+            Self::Write(e) => defmt::write!(f, "the output could not be written to: {}", e),
+        }
+    }
+}
+
+impl<'a> JsonWebToken<'a> {
+
+    /// serialize a JsonWebToken with a serde payload & the specified algorithm & secret into the provided output.
+    /// the payload is serialized into json_buffer before being streamed through the base64url encoder
+    pub fn serialize_serde<C: Serialize + ?Sized, T: Write>(payload: &C, output: T, algorithm: JwtType, secret: &[u8], json_buffer: &mut [u8]) -> Result<usize,SerdeSerializeFailure<T::Error>> {
+        let json_len = serde_json_core::to_slice(payload, json_buffer).map_err(SerdeSerializeFailure::Json)?;
+        let header = get_jose_header(default_typ(&algorithm), None, &algorithm);
+        serialize_jwt_with_header(output, header.as_slice(), &RawJsonPayload(json_buffer.split_at(json_len).0), &algorithm, secret)
+            .map_err(SerdeSerializeFailure::Write)
+    }
+
+    /// attempt to deserialize the payload of a JsonWebToken into a serde type with the specified algorithm & secret from the provided data, applying the provided validation.
+    /// the claims are validated without modifying the payload, so they may contain nested objects & arrays, including an `aud` array
+    pub fn deserialize_serde<C: Deserialize<'a>>(data: &'a [u8], base64buffer: &'a mut [u8], algorithm: JwtType, secret: &[u8], validation: &Validation<'_>) -> Result<C,JwtParseFailure> {
+        let (_decoded_header,decoded_claims) = decode_jwt_payload(data, &algorithm, secret, base64buffer, validation)?;
+        let decoded_claims = &*decoded_claims;
+        let members = Members::new(decoded_claims).map_err(JwtParseFailure::InvalidClaims)?;
+        validation.validate_claim_values(members.map(|(key, value)| (key, claim_value(value))))?;
+        match serde_json_core::from_slice(decoded_claims) {
+            Ok((claims, _num_bytes)) => Ok(claims),
+            Err(_) => Err(JwtParseFailure::InvalidSerdeClaims),
+        }
+    }
+}
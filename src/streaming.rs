@@ -0,0 +1,257 @@
+use base64::{prelude::BASE64_URL_SAFE_NO_PAD, DecodeSliceError, Engine};
+use embedded_io::Read;
+use lil_json::JsonField;
+
+#[cfg(feature = "signature")]
+use hmac::{Hmac, Mac};
+#[cfg(feature = "signature")]
+use sha2::{Sha256, Sha384, Sha512};
+
+use crate::{base64_writer::{BASE64_INPUT_BLOCK_SIZE, BASE64_OUTPUT_BLOCK_SIZE}, base64url_len, check_base64url_byte, check_num_dots, in_place::offset_decode_error, parse_claims, parse_jose_header, JwtParseFailure, JwtSegment, JwtType, Validation};
+#[cfg(feature = "unsecured")]
+use crate::verify_empty_signature;
+#[cfg(feature = "signature")]
+use crate::{verify_mac, SignatureAlgorithm, MAX_SIGNATURE_LEN};
+
+/// the number of bytes requested from the reader at a time
+const READ_CHUNK_SIZE: usize = 64;
+
+/// the longest base64url encoded signature that is accepted
+#[cfg(feature = "signature")]
+const MAX_ENCODED_SIGNATURE_LEN: usize = base64url_len(MAX_SIGNATURE_LEN);
+#[cfg(not(feature = "signature"))]
+const MAX_ENCODED_SIGNATURE_LEN: usize = base64url_len(0);
+
+/// a failure to deserialize a JWT from a reader
+#[derive(Debug)]
+pub enum JwtReadFailure<E> {
+    /// the reader failed
+    Read(E),
+    /// the data that was read is not a valid JWT
+    Parse(JwtParseFailure),
+}
+
+impl<E> From<JwtParseFailure> for JwtReadFailure<E> {
+    fn from(failure: JwtParseFailure) -> Self {
+        Self::Parse(failure)
+    }
+}
+
+impl<E: core::fmt::Debug> core::fmt::Display for JwtReadFailure<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> Result<(), core::fmt::Error> {
+        match self {
+            Self::Read(e) => write!(f, "the reader failed: {:?}", e),
+            Self::Parse(failure) => core::fmt::Display::fmt(failure, f),
+        }
+    }
+}
+
+impl<E: core::fmt::Debug> core::error::Error for JwtReadFailure<E> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Read(_) => None,
+            Self::Parse(failure) => Some(failure),
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<E: defmt::Format> defmt::Format for JwtReadFailure<E> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            Self::Read(e) => defmt::write!(f, "the reader failed: {}", e),
+            Self::Parse(failure) => defmt::write!(f, "{}", failure),
+        }
+    }
+}
+
+/// a MAC that is updated as the signing input is read
+enum StreamingMac {
+    #[cfg(feature = "unsecured")]
+    Unsecured,
+    #[cfg(feature = "signature")]
+    HS256(Hmac<Sha256>),
+    #[cfg(feature = "signature")]
+    HS384(Hmac<Sha384>),
+    #[cfg(feature = "signature")]
+    HS512(Hmac<Sha512>),
+}
+
+impl StreamingMac {
+    fn new(algorithm: &JwtType, #[cfg_attr(not(feature = "signature"), allow(unused_variables))] secret: &[u8]) -> Self {
+        match algorithm {
+            #[cfg(feature = "unsecured")]
+            JwtType::Unsecured(_) => Self::Unsecured,
+            #[cfg(feature = "signature")]
+            JwtType::Signed(SignatureAlgorithm::HS256) => Self::HS256(Hmac::new_from_slice(secret).expect("invalid HS256 secret")),
+            #[cfg(feature = "signature")]
+            JwtType::Signed(SignatureAlgorithm::HS384) => Self::HS384(Hmac::new_from_slice(secret).expect("invalid HS384 secret")),
+            #[cfg(feature = "signature")]
+            JwtType::Signed(SignatureAlgorithm::HS512) => Self::HS512(Hmac::new_from_slice(secret).expect("invalid HS512 secret")),
+            JwtType::Encrypted(e) => match *e {},
+        }
+    }
+
+    fn update(&mut self, #[cfg_attr(not(feature = "signature"), allow(unused_variables))] data: &[u8]) {
+        match *self {
+            #[cfg(feature = "unsecured")]
+            Self::Unsecured => {},
+            #[cfg(feature = "signature")]
+            Self::HS256(ref mut mac) => mac.update(data),
+            #[cfg(feature = "signature")]
+            Self::HS384(ref mut mac) => mac.update(data),
+            #[cfg(feature = "signature")]
+            Self::HS512(ref mut mac) => mac.update(data),
+        }
+    }
+
+    fn verify(self, signature_b64: &[u8]) -> Result<(),JwtParseFailure> {
+        match self {
+            #[cfg(feature = "unsecured")]
+            Self::Unsecured => verify_empty_signature(signature_b64),
+            #[cfg(feature = "signature")]
+            Self::HS256(mac) => verify_mac(mac, signature_b64),
+            #[cfg(feature = "signature")]
+            Self::HS384(mac) => verify_mac(mac, signature_b64),
+            #[cfg(feature = "signature")]
+            Self::HS512(mac) => verify_mac(mac, signature_b64),
+        }
+    }
+}
+
+/// incrementally decodes one base64url segment into an output buffer
+struct SegmentDecoder {
+    segment: JwtSegment,
+    block: [u8; BASE64_OUTPUT_BLOCK_SIZE],
+    block_len: usize,
+    segment_len: usize,
+    output_position: usize,
+}
+
+impl SegmentDecoder {
+    fn new(segment: JwtSegment, output_position: usize) -> Self {
+        Self { segment, block: [0_u8; BASE64_OUTPUT_BLOCK_SIZE], block_len: 0, segment_len: 0, output_position }
+    }
+
+    fn push(&mut self, byte: u8, output: &mut [u8]) -> Result<(),JwtParseFailure> {
+        self.block[self.block_len] = byte;
+        self.block_len += 1;
+        self.segment_len += 1;
+        if self.block_len == BASE64_OUTPUT_BLOCK_SIZE {
+            self.decode_block(output)?;
+        }
+        Ok(())
+    }
+
+    /// decode any partial final block & return the position after the decoded segment
+    fn finish(mut self, output: &mut [u8]) -> Result<usize,JwtParseFailure> {
+        if self.block_len > 0 {
+            self.decode_block(output)?;
+        }
+        Ok(self.output_position)
+    }
+
+    fn decode_block(&mut self, output: &mut [u8]) -> Result<(),JwtParseFailure> {
+        let mut decoded_block = [0_u8; BASE64_INPUT_BLOCK_SIZE];
+        let n = match BASE64_URL_SAFE_NO_PAD.decode_slice(self.block.split_at(self.block_len).0, &mut decoded_block) {
+            Ok(n) => n,
+            Err(DecodeSliceError::OutputSliceTooSmall) => unreachable!("a base64 block decodes to at most 3 bytes"),
+            Err(DecodeSliceError::DecodeError(e)) => return Err(JwtParseFailure::InvalidBase64Url(self.segment, offset_decode_error(e, self.segment_len - self.block_len, self.segment_len))),
+        };
+        match output.get_mut(self.output_position..self.output_position + n) {
+            Some(destination) => destination.copy_from_slice(decoded_block.split_at(n).0),
+            None => return Err(JwtParseFailure::Base64BufferTooSmall),
+        }
+        self.output_position += n;
+        self.block_len = 0;
+        Ok(())
+    }
+}
+
+enum Segment {
+    Header(SegmentDecoder),
+    Body(usize,SegmentDecoder),
+    Signature(usize,usize),
+}
+
+/// deserialize a JWT as it is read from reader, computing the MAC over the signing input & decoding the header & claims into buffer as data arrives.
+/// the signature is verified once the reader reaches the end of its data, before the header & claims are parsed
+pub fn deserialize_jwt_from_reader<'a, R: Read>(mut reader: R, buffer: &'a mut [u8], claims_buffer: &mut [JsonField<'a,'a>], algorithm: &JwtType, secret: &[u8], validation: &Validation<'_>) -> Result<usize,JwtReadFailure<R::Error>> {
+    let mut mac = StreamingMac::new(algorithm, secret);
+    let mut segment = Segment::Header(SegmentDecoder::new(JwtSegment::Header, 0));
+    let mut signature_b64 = [0_u8; MAX_ENCODED_SIGNATURE_LEN];
+    let mut signature_len = 0;
+    let mut chunk = [0_u8; READ_CHUNK_SIZE];
+    let mut offset = 0;
+    let mut num_dots = 0;
+    loop {
+        let n = reader.read(&mut chunk).map_err(JwtReadFailure::Read)?;
+        if n == 0 {
+            break;
+        }
+        let chunk = chunk.split_at(n).0;
+        let mut signing_input_end = n;
+        for (i, &byte) in chunk.iter().enumerate() {
+            if byte == b'.' {
+                num_dots += 1;
+            } else {
+                check_base64url_byte(offset + i, byte)?;
+            }
+            segment = match (segment, byte) {
+                (Segment::Header(header), b'.') => {
+                    if header.segment_len == 0 {
+                        return Err(JwtParseFailure::EmptyHeader.into());
+                    }
+                    let header_end = header.finish(buffer)?;
+                    Segment::Body(header_end, SegmentDecoder::new(JwtSegment::Payload, header_end))
+                },
+                (Segment::Body(header_end, body), b'.') => {
+                    if body.segment_len == 0 {
+                        return Err(JwtParseFailure::EmptyPayload.into());
+                    }
+                    if signing_input_end == n {
+                        signing_input_end = i;
+                    }
+                    Segment::Signature(header_end, body.finish(buffer)?)
+                },
+                // any further dots are counted & reported once the whole token has been read
+                (Segment::Signature(header_end, body_end), b'.') => Segment::Signature(header_end, body_end),
+                (Segment::Header(mut header), byte) => {
+                    header.push(byte, buffer)?;
+                    Segment::Header(header)
+                },
+                (Segment::Body(header_end, mut body), byte) => {
+                    body.push(byte, buffer)?;
+                    Segment::Body(header_end, body)
+                },
+                (Segment::Signature(header_end, body_end), byte) => {
+                    // an overlong signature is rejected after the dots have been counted, so that a JWE is reported as one
+                    if let Some(b) = signature_b64.get_mut(signature_len) {
+                        *b = byte;
+                    }
+                    signature_len += 1;
+                    Segment::Signature(header_end, body_end)
+                },
+            };
+        }
+        let already_signed = matches!(segment, Segment::Signature(..)) && signing_input_end == n;
+        if !already_signed {
+            mac.update(chunk.split_at(signing_input_end).0);
+        }
+        offset += n;
+    }
+    check_num_dots(num_dots)?;
+    let (header_end, body_end) = match segment {
+        Segment::Signature(header_end, body_end) => (header_end, body_end),
+        _ => unreachable!("a token with 2 dots ends in its signature segment"),
+    };
+    if signature_len > signature_b64.len() {
+        return Err(JwtParseFailure::InvalidSignature.into());
+    }
+    mac.verify(signature_b64.split_at(signature_len).0)?;
+    let (decoded_header, remaining_buffer) = buffer.split_at_mut(header_end);
+    parse_jose_header(decoded_header, algorithm, validation)?;
+    let (num_claims,audience_array) = parse_claims(remaining_buffer.split_at_mut(body_end - header_end).0, claims_buffer)?;
+    validation.validate_claims(claims_buffer.split_at(num_claims).0, audience_array)?;
+    Ok(num_claims)
+}
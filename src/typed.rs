@@ -0,0 +1,140 @@
+use lil_json::{JsonField, JsonValue};
+
+use crate::JwtParseFailure;
+
+/// a failure to represent a typed value as a set of JWT claims
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum ToClaimsFailure {
+    /// the claims buffer holds fewer than MAX_CLAIMS fields
+    BufferTooSmall,
+    /// the value of the named claim can't be represented as a JSON value, e.g. an integer above i64::MAX
+    InvalidClaim(&'static str),
+}
+
+impl core::fmt::Display for ToClaimsFailure {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> Result<(), core::fmt::Error> {
+        match self {
+            Self::BufferTooSmall => f.write_str("the claims buffer is too small"),
+            Self::InvalidClaim(name) => write!(f, "the {} claim can't be represented as a JSON value", name),
+        }
+    }
+}
+
+impl core::error::Error for ToClaimsFailure {}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for ToClaimsFailure {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "{}", defmt::Display2Format(self))
+    }
+}
+
+/// a type that can be represented as a set of JWT claims without copying or allocating
+pub trait ToClaims {
+    /// the maximum number of claims written by to_claims
+    const MAX_CLAIMS: usize;
+    /// write the claims that represent this value into claims_buffer & return how many were written.
+    /// claims_buffer must hold at least MAX_CLAIMS fields.
+    fn to_claims<'a>(&'a self, claims_buffer: &mut [JsonField<'a,'a>]) -> Result<usize,ToClaimsFailure>;
+}
+
+/// a type that can be constructed from a set of JWT claims, borrowing from them where possible
+pub trait FromClaims<'a>: Sized {
+    /// construct a value from the provided claims
+    fn from_claims(claims: &[JsonField<'a,'a>]) -> Result<Self,JwtParseFailure>;
+}
+
+/// a type that can be written as the value of a single claim
+pub trait ToClaimValue {
+    /// the JSON value of the claim with the provided name, or None if the claim should be omitted
+    fn to_claim_value(&self, name: &'static str) -> Result<Option<JsonValue<'_>>,ToClaimsFailure>;
+}
+
+/// a type that can be read from the value of a single claim
+pub trait FromClaimValue<'a>: Sized {
+    /// read the value of the claim with the provided name, which is None if the claim is absent
+    fn from_claim_value(value: Option<JsonValue<'a>>, name: &'static str) -> Result<Self,JwtParseFailure>;
+}
+
+/// find the value of the first claim with the provided name
+pub fn get_claim<'a>(claims: &[JsonField<'_,'a>], name: &str) -> Option<JsonValue<'a>> {
+    claims.iter().find(|claim| claim.key == name).map(|claim| claim.value)
+}
+
+impl ToClaimValue for &str {
+    fn to_claim_value(&self, _name: &'static str) -> Result<Option<JsonValue<'_>>,ToClaimsFailure> {
+        Ok(Some(JsonValue::String(self)))
+    }
+}
+
+impl<'a> FromClaimValue<'a> for &'a str {
+    fn from_claim_value(value: Option<JsonValue<'a>>, name: &'static str) -> Result<Self,JwtParseFailure> {
+        match value {
+            Some(JsonValue::String(s)) => Ok(s),
+            Some(_) => Err(JwtParseFailure::InvalidClaim(name)),
+            None => Err(JwtParseFailure::MissingClaim(name)),
+        }
+    }
+}
+
+impl ToClaimValue for bool {
+    fn to_claim_value(&self, _name: &'static str) -> Result<Option<JsonValue<'_>>,ToClaimsFailure> {
+        Ok(Some(JsonValue::Boolean(*self)))
+    }
+}
+
+impl<'a> FromClaimValue<'a> for bool {
+    fn from_claim_value(value: Option<JsonValue<'a>>, name: &'static str) -> Result<Self,JwtParseFailure> {
+        match value {
+            Some(JsonValue::Boolean(b)) => Ok(b),
+            Some(_) => Err(JwtParseFailure::InvalidClaim(name)),
+            None => Err(JwtParseFailure::MissingClaim(name)),
+        }
+    }
+}
+
+/// integers are written as JSON numbers, failing with InvalidClaim outside the bounds of i64
+macro_rules! impl_integer_claim_value {
+    ($($t:ty),*) => {
+        $(
+            impl ToClaimValue for $t {
+                fn to_claim_value(&self, name: &'static str) -> Result<Option<JsonValue<'_>>,ToClaimsFailure> {
+                    match i64::try_from(*self) {
+                        Ok(n) => Ok(Some(JsonValue::Number(n))),
+                        Err(_) => Err(ToClaimsFailure::InvalidClaim(name)),
+                    }
+                }
+            }
+
+            impl<'a> FromClaimValue<'a> for $t {
+                fn from_claim_value(value: Option<JsonValue<'a>>, name: &'static str) -> Result<Self,JwtParseFailure> {
+                    match value {
+                        Some(JsonValue::Number(n)) => <$t>::try_from(n).map_err(|_| JwtParseFailure::InvalidClaim(name)),
+                        Some(_) => Err(JwtParseFailure::InvalidClaim(name)),
+                        None => Err(JwtParseFailure::MissingClaim(name)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_integer_claim_value!(i64, u64, i32, u32, i16, u16, i8, u8, isize, usize);
+
+impl<T: ToClaimValue> ToClaimValue for Option<T> {
+    fn to_claim_value(&self, name: &'static str) -> Result<Option<JsonValue<'_>>,ToClaimsFailure> {
+        match self {
+            Some(value) => value.to_claim_value(name),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<'a, T: FromClaimValue<'a>> FromClaimValue<'a> for Option<T> {
+    fn from_claim_value(value: Option<JsonValue<'a>>, name: &'static str) -> Result<Self,JwtParseFailure> {
+        match value {
+            Some(value) => T::from_claim_value(Some(value), name).map(Some),
+            None => Ok(None),
+        }
+    }
+}
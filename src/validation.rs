@@ -0,0 +1,308 @@
+use lil_json::{JsonField, JsonValue};
+
+use crate::members::string_array_contains;
+use crate::{Clock, JwtParseFailure, ReplayGuard};
+
+/// the value of a claim as seen by the claim checks
+#[derive(Clone,Copy)]
+pub(crate) enum ClaimValue<'a> {
+    String(&'a str),
+    Number(i64),
+    /// a JSON array, which is left undecoded
+    Array(&'a [u8]),
+    /// a boolean, null, non-integer number or nested object
+    Other,
+}
+
+impl<'a> From<JsonValue<'a>> for ClaimValue<'a> {
+    fn from(value: JsonValue<'a>) -> Self {
+        match value {
+            JsonValue::String(s) => Self::String(s),
+            JsonValue::Number(n) => Self::Number(n),
+            JsonValue::Boolean(_) => Self::Other,
+        }
+    }
+}
+
+/// a claim name & its value
+pub(crate) type Claim<'a> = (&'a str,ClaimValue<'a>);
+
+/// where the current time used to check time claims comes from
+#[derive(Clone,Copy,Default)]
+enum TimeSource<'v> {
+    #[default]
+    Unchecked,
+    Fixed(u64),
+    Clock(&'v dyn Clock),
+}
+
+impl core::fmt::Debug for TimeSource<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Unchecked => f.write_str("Unchecked"),
+            Self::Fixed(now) => f.debug_tuple("Fixed").field(now).finish(),
+            Self::Clock(_) => f.write_str("Clock"),
+        }
+    }
+}
+
+/// additional checks that are applied to a JsonWebToken while it is being deserialized
+#[derive(Clone,Copy,Default)]
+pub struct Validation<'v> {
+    typ: Option<&'v str>,
+    time: TimeSource<'v>,
+    leeway: u64,
+    max_age: Option<u64>,
+    issuers: Option<&'v [&'v str]>,
+    audience: Option<&'v str>,
+    subject: Option<&'v str>,
+    required_claims: &'v [&'static str],
+    replay_guard: Option<&'v dyn ReplayGuard>,
+}
+
+impl core::fmt::Debug for Validation<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Validation")
+            .field("typ", &self.typ)
+            .field("time", &self.time)
+            .field("leeway", &self.leeway)
+            .field("max_age", &self.max_age)
+            .field("issuers", &self.issuers)
+            .field("audience", &self.audience)
+            .field("subject", &self.subject)
+            .field("required_claims", &self.required_claims)
+            .field("replay_guard", &self.replay_guard.is_some())
+            .finish()
+    }
+}
+
+impl<'v> Validation<'v> {
+
+    /// construct a Validation that performs no additional checks
+    pub const fn new() -> Self {
+        Self {
+            typ: None,
+            time: TimeSource::Unchecked,
+            leeway: 0,
+            max_age: None,
+            issuers: None,
+            audience: None,
+            subject: None,
+            required_claims: &[],
+            replay_guard: None,
+        }
+    }
+
+    /// check the `exp`, `nbf` & `iat` claims against the provided current time (seconds since the unix epoch)
+    pub const fn at_time(mut self, now: u64) -> Self {
+        self.time = TimeSource::Fixed(now);
+        self
+    }
+
+    /// check the `exp`, `nbf` & `iat` claims against the time reported by the provided Clock
+    pub const fn with_clock(mut self, clock: &'v dyn Clock) -> Self {
+        self.time = TimeSource::Clock(clock);
+        self
+    }
+
+    /// allow the time claims to be off by up to the provided number of seconds to account for clock skew
+    pub const fn with_leeway(mut self, leeway: u64) -> Self {
+        self.leeway = leeway;
+        self
+    }
+
+    /// require an `iat` claim that is no more than the provided number of seconds old
+    pub const fn with_max_age(mut self, max_age: u64) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// require the `typ` header to be present & to match the provided media type (RFC 8725 section 3.11)
+    pub const fn require_typ(mut self, typ: &'v str) -> Self {
+        self.typ = Some(typ);
+        self
+    }
+
+    /// require the `iss` claim to be one of the provided issuers
+    pub const fn with_issuers(mut self, issuers: &'v [&'v str]) -> Self {
+        self.issuers = Some(issuers);
+        self
+    }
+
+    /// require the `aud` claim to be the provided audience, or an array containing it
+    pub const fn with_audience(mut self, audience: &'v str) -> Self {
+        self.audience = Some(audience);
+        self
+    }
+
+    /// require the `sub` claim to exactly match the provided subject
+    pub const fn with_subject(mut self, subject: &'v str) -> Self {
+        self.subject = Some(subject);
+        self
+    }
+
+    /// require each of the provided claims to be present
+    pub const fn with_required_claims(mut self, required_claims: &'v [&'static str]) -> Self {
+        self.required_claims = required_claims;
+        self
+    }
+
+    /// require a `jti` claim that has not been recorded by the provided ReplayGuard within its validity window.
+    /// the window ends at the `exp` claim plus the leeway, so an `exp` claim & a time source (at_time or with_clock) are also required
+    pub const fn with_replay_guard(mut self, replay_guard: &'v dyn ReplayGuard) -> Self {
+        self.replay_guard = Some(replay_guard);
+        self
+    }
+
+    /// the `aud` claim value that is required, if any
+    pub const fn required_audience(&self) -> Option<&'v str> {
+        self.audience
+    }
+
+    /// the `typ` header value that is required, if any
+    pub const fn required_typ(&self) -> Option<&'v str> {
+        self.typ
+    }
+
+    /// apply the claim checks of this Validation to the provided claims & the raw `aud` array that was left out of them, if any.
+    /// duplicate claim names are always rejected (RFC 7519 section 4)
+    pub(crate) fn validate_claims(&self, claims: &[JsonField<'_,'_>], audience_array: Option<&[u8]>) -> Result<(),JwtParseFailure> {
+        let audience_array = audience_array.map(|array| ("aud", ClaimValue::Array(array)));
+        self.validate_claim_values(claims.iter().map(|claim| (claim.key, ClaimValue::from(claim.value))).chain(audience_array))
+    }
+
+    /// apply the claim checks of this Validation to claims that may not be representable as lil-json fields
+    pub(crate) fn validate_claim_values<'c, I: Iterator<Item=Claim<'c>> + Clone>(&self, claims: I) -> Result<(),JwtParseFailure> {
+        for (i, (key, _)) in claims.clone().enumerate() {
+            if claims.clone().skip(i + 1).any(|(other, _)| other == key) {
+                return Err(JwtParseFailure::DuplicateClaim);
+            }
+        }
+        for required_claim in self.required_claims {
+            if !claims.clone().any(|(key, _)| key == *required_claim) {
+                return Err(JwtParseFailure::MissingClaim(required_claim));
+            }
+        }
+        if let Some(issuers) = self.issuers {
+            match find_string_claim(claims.clone(), "iss", JwtParseFailure::InvalidIssuer)? {
+                Some(iss) if issuers.contains(&iss) => {},
+                _ => return Err(JwtParseFailure::InvalidIssuer),
+            }
+        }
+        if let Some(audience) = self.audience {
+            match find_claim(claims.clone(), "aud") {
+                Some(ClaimValue::String(aud)) if aud == audience => {},
+                Some(ClaimValue::Array(array)) if string_array_contains(array, audience) => {},
+                _ => return Err(JwtParseFailure::InvalidAudience),
+            }
+        }
+        if let Some(subject) = self.subject {
+            match find_string_claim(claims.clone(), "sub", JwtParseFailure::InvalidSubject)? {
+                Some(sub) if sub == subject => {},
+                _ => return Err(JwtParseFailure::InvalidSubject),
+            }
+        }
+        let now = self.current_time()?;
+        if let Some(now) = now {
+            self.validate_time_claims(claims.clone(), now)?;
+        }
+        if let Some(replay_guard) = self.replay_guard {
+            let now = match now {
+                Some(now) => now,
+                None => return Err(JwtParseFailure::ReplayGuardWithoutTime),
+            };
+            let jti = match find_string_claim(claims.clone(), "jti", JwtParseFailure::InvalidTokenId)? {
+                Some(jti) => jti,
+                None => return Err(JwtParseFailure::MissingClaim("jti")),
+            };
+            let exp = match find_time_claim(claims, "exp")? {
+                Some(exp) => exp,
+                None => return Err(JwtParseFailure::MissingClaim("exp")),
+            };
+            replay_guard.check_and_record(jti, exp.saturating_add(to_numeric_date(self.leeway)), now)?;
+        }
+        Ok(())
+    }
+
+    /// the current time according to the configured time source, if any
+    fn current_time(&self) -> Result<Option<i64>,JwtParseFailure> {
+        match self.time {
+            TimeSource::Unchecked => Ok(None),
+            TimeSource::Fixed(now) => Ok(Some(to_numeric_date(now))),
+            TimeSource::Clock(clock) => match clock.now() {
+                Some(now) => Ok(Some(to_numeric_date(now))),
+                None => Err(JwtParseFailure::ClockUnavailable),
+            },
+        }
+    }
+
+    fn validate_time_claims<'c, I: Iterator<Item=Claim<'c>> + Clone>(&self, claims: I, now: i64) -> Result<(),JwtParseFailure> {
+        let leeway = to_numeric_date(self.leeway);
+        if let Some(exp) = find_time_claim(claims.clone(), "exp")? {
+            if now.saturating_sub(leeway) >= exp {
+                return Err(JwtParseFailure::Expired);
+            }
+        }
+        if let Some(nbf) = find_time_claim(claims.clone(), "nbf")? {
+            if now.saturating_add(leeway) < nbf {
+                return Err(JwtParseFailure::NotYetValid);
+            }
+        }
+        let iat = find_time_claim(claims, "iat")?;
+        if let Some(iat) = iat {
+            if iat > now.saturating_add(leeway) {
+                return Err(JwtParseFailure::IssuedInFuture);
+            }
+        }
+        if let Some(max_age) = self.max_age {
+            match iat {
+                Some(iat) if now.saturating_sub(iat) <= to_numeric_date(max_age).saturating_add(leeway) => {},
+                _ => return Err(JwtParseFailure::TooOld),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// convert a number of seconds into the signed representation used by JSON numbers
+pub(crate) fn to_numeric_date(seconds: u64) -> i64 {
+    i64::try_from(seconds).unwrap_or(i64::MAX)
+}
+
+/// find the value of a claim. duplicate claims have already been rejected by validate_claim_values
+fn find_claim<'c>(mut claims: impl Iterator<Item=Claim<'c>>, name: &str) -> Option<ClaimValue<'c>> {
+    claims.find(|(key, _)| *key == name).map(|(_, value)| value)
+}
+
+/// find the value of a NumericDate claim (RFC 7519 section 2), rejecting non-numeric values
+fn find_time_claim<'c>(claims: impl Iterator<Item=Claim<'c>>, name: &str) -> Result<Option<i64>,JwtParseFailure> {
+    match find_claim(claims, name) {
+        Some(ClaimValue::Number(n)) => Ok(Some(n)),
+        Some(_) => Err(JwtParseFailure::InvalidTimeClaim),
+        None => Ok(None),
+    }
+}
+
+/// find the value of a StringOrURI claim, failing with the provided error on non-string values
+fn find_string_claim<'c>(claims: impl Iterator<Item=Claim<'c>>, name: &str, failure: JwtParseFailure) -> Result<Option<&'c str>,JwtParseFailure> {
+    match find_claim(claims, name) {
+        Some(ClaimValue::String(s)) => Ok(Some(s)),
+        Some(_) => Err(failure),
+        None => Ok(None),
+    }
+}
+
+const MEDIA_TYPE_PREFIX: &str = "application/";
+
+/// remove the optional `application/` prefix from a media type (RFC 7515 section 4.1.9)
+fn strip_media_type_prefix(media_type: &str) -> &str {
+    match media_type.get(..MEDIA_TYPE_PREFIX.len()) {
+        Some(prefix) if prefix.eq_ignore_ascii_case(MEDIA_TYPE_PREFIX) => &media_type[MEDIA_TYPE_PREFIX.len()..],
+        _ => media_type,
+    }
+}
+
+/// compare two `typ` header values case-insensitively, ignoring any `application/` prefix
+pub(crate) fn typ_matches(actual: &str, expected: &str) -> bool {
+    strip_media_type_prefix(actual).eq_ignore_ascii_case(strip_media_type_prefix(expected))
+}
@@ -0,0 +1,550 @@
+//! `cargo xtask footprint` builds the sample firmware in footprint/ for a Cortex-M4F target once per feature combination,
+//! then reports the .text size of each binary & the worst-case stack usage of serialize_jwt & deserialize_jwt.
+//! it fails when a measurement exceeds its threshold or can't be taken.
+//!
+//! the worst-case stack usage comes from a nightly build with `-Z emit-stack-sizes` & `-Z build-std`: LLVM records the frame size
+//! of each function in a .stack_sizes section, & the call graph is read from the branch instructions in .text.
+//! a call through a function pointer is assumed to go to any function whose address is taken.
+//! recursion makes the stack usage unbounded, which is reported as a failure.
+//!
+//! requirements:
+//! - `rustup target add thumbv7em-none-eabihf`
+//! - for stack usage, `rustup toolchain install nightly --target thumbv7em-none-eabihf --component rust-src`. pass --no-stack to only report code size.
+
+use std::{collections::{BTreeMap, HashMap}, env, fs, path::{Path, PathBuf}, process::{exit, Command}};
+
+const TARGET: &str = "thumbv7em-none-eabihf";
+
+/// a sample binary & the features it is built with
+struct Sample {
+    bin: &'static str,
+    features: &'static [&'static str],
+    max_text_bytes: usize,
+    max_serialize_stack_bytes: usize,
+    max_deserialize_stack_bytes: usize,
+}
+
+/// the thresholds are about 10% above the sizes measured with rustc 1.95 (.text) & nightly 1.97 (stack):
+/// - unsecured: 13352 bytes of .text, 712 & 608 bytes of stack
+/// - hs256: 19248 bytes of .text, 1548 & 1500 bytes of stack
+/// - hmac: 52536 bytes of .text, 2528 & 2420 bytes of stack
+/// - all: 53660 bytes of .text, 2528 & 2420 bytes of stack
+const SAMPLES: [Sample; 4] = [
+    // only unsecured tokens, without any signature support compiled in
+    Sample { bin: "unsecured", features: &["unsecured"], max_text_bytes: 15 * 1024, max_serialize_stack_bytes: 784, max_deserialize_stack_bytes: 672 },
+    // signature support without unsecured tokens, using HS256 only
+    Sample { bin: "hs256", features: &["signature"], max_text_bytes: 21 * 1024, max_serialize_stack_bytes: 1728, max_deserialize_stack_bytes: 1664 },
+    // signature support without unsecured tokens, using all of the HMAC algorithms
+    Sample { bin: "hmac", features: &["signature"], max_text_bytes: 57 * 1024, max_serialize_stack_bytes: 2816, max_deserialize_stack_bytes: 2688 },
+    // the default features, using unsecured tokens & all of the HMAC algorithms
+    Sample { bin: "all", features: &["signature", "unsecured"], max_text_bytes: 58 * 1024, max_serialize_stack_bytes: 2816, max_deserialize_stack_bytes: 2688 },
+];
+
+/// the functions in footprint/src/lib.rs that wrap serialize_jwt & deserialize_jwt
+const SERIALIZE_FN: &str = "sample_serialize";
+const DESERIALIZE_FN: &str = "sample_deserialize";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("footprint") => footprint(!args.iter().any(|arg| arg == "--no-stack")),
+        _ => {
+            eprintln!("usage: cargo xtask footprint [--no-stack]");
+            exit(2);
+        },
+    }
+}
+
+fn footprint_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap().join("footprint")
+}
+
+fn footprint(measure_stack: bool) {
+    let mut failures = Vec::new();
+    println!("{:<10} {:<20} {:>10} {:>16} {:>18}", "sample", "features", ".text", "serialize stack", "deserialize stack");
+    for sample in &SAMPLES {
+        let text_bytes = build(sample).and_then(|elf_path| text_size(&elf_path));
+        let text_bytes = check(&mut failures, sample.bin, ".text", text_bytes, sample.max_text_bytes);
+        let (serialize_stack, deserialize_stack) = if measure_stack {
+            match build_with_stack_sizes(sample).and_then(|elf_path| CallGraph::read(&elf_path)) {
+                Ok(call_graph) => (
+                    check(&mut failures, sample.bin, "serialize stack", call_graph.worst_case_stack(SERIALIZE_FN), sample.max_serialize_stack_bytes),
+                    check(&mut failures, sample.bin, "deserialize stack", call_graph.worst_case_stack(DESERIALIZE_FN), sample.max_deserialize_stack_bytes),
+                ),
+                Err(e) => {
+                    failures.push(format!("{}: stack usage: {e}", sample.bin));
+                    ("?".into(), "?".into())
+                },
+            }
+        } else {
+            ("-".into(), "-".into())
+        };
+        let features = if sample.features.is_empty() { "(none)".into() } else { sample.features.join(",") };
+        println!("{:<10} {:<20} {:>10} {:>16} {:>18}", sample.bin, features, text_bytes, serialize_stack, deserialize_stack);
+    }
+    if !failures.is_empty() {
+        for failure in &failures {
+            eprintln!("{failure}");
+        }
+        exit(1);
+    }
+}
+
+/// record a failure if a measurement couldn't be taken or exceeds its threshold, & return the measurement to report
+fn check(failures: &mut Vec<String>, bin: &str, what: &str, measured: Result<usize,String>, threshold: usize) -> String {
+    match measured {
+        Ok(measured) => {
+            if measured > threshold {
+                failures.push(format!("{bin}: {what} is {measured} bytes, which exceeds the threshold of {threshold} bytes"));
+            }
+            measured.to_string()
+        },
+        Err(e) => {
+            failures.push(format!("{bin}: {what}: {e}"));
+            "?".into()
+        },
+    }
+}
+
+fn cargo() -> Command {
+    let mut command = Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()));
+    // run from footprint/ so its .cargo/config.toml applies
+    command.current_dir(footprint_dir());
+    command
+}
+
+/// cargo on the nightly toolchain. it is run through rustup because $CARGO is the current toolchain's cargo rather than the rustup proxy,
+/// so it can't take `+nightly`
+fn nightly_cargo() -> Command {
+    let mut command = Command::new("rustup");
+    command.args(["run", "nightly", "cargo"]).env_remove("RUSTUP_TOOLCHAIN").current_dir(footprint_dir());
+    command
+}
+
+fn run(mut command: Command) -> Result<String,String> {
+    let output = command.output().map_err(|e| format!("failed to run {command:?}: {e}"))?;
+    if !output.status.success() {
+        return Err(format!("{command:?} failed with {}:\n{}", output.status, String::from_utf8_lossy(&output.stderr)));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn build_args(sample: &Sample) -> Vec<String> {
+    ["build", "--release", "--target", TARGET, "--bin", sample.bin, "--no-default-features", "--features", &sample.features.join(",")].map(String::from).into()
+}
+
+/// build a sample binary & return its path
+fn build(sample: &Sample) -> Result<PathBuf,String> {
+    let mut command = cargo();
+    command.args(build_args(sample));
+    run(command)?;
+    Ok(footprint_dir().join("target").join(TARGET).join("release").join(sample.bin))
+}
+
+/// build a sample binary with a .stack_sizes section & return its path.
+/// it goes to its own target directory so it doesn't invalidate the build that .text is measured on
+fn build_with_stack_sizes(sample: &Sample) -> Result<PathBuf,String> {
+    let mut command = nightly_cargo();
+    // added to the rustflags in footprint/.cargo/config.toml
+    command.env("CARGO_TARGET_THUMBV7EM_NONE_EABIHF_RUSTFLAGS", "-Z emit-stack-sizes");
+    // core & compiler_builtins are rebuilt so that functions such as memcpy have stack sizes too
+    command.args(build_args(sample)).args(["-Z", "build-std=core,compiler_builtins", "--target-dir", "target/stack"]);
+    run(command)?;
+    Ok(footprint_dir().join("target").join("stack").join(TARGET).join("release").join(sample.bin))
+}
+
+/// the size of the .text section of a 32 bit little endian ELF file
+fn text_size(elf_path: &Path) -> Result<usize,String> {
+    let elf = fs::read(elf_path).map_err(|e| format!("failed to read {}: {e}", elf_path.display()))?;
+    elf32_section_size(&elf, ".text").ok_or_else(|| format!("{} has no .text section", elf_path.display()))
+}
+
+fn elf32_section_size(elf: &[u8], section_name: &str) -> Option<usize> {
+    elf32_sections(elf)?.into_iter().find(|section| section.name == section_name).map(|section| section.size)
+}
+
+/// a section of an ELF file
+#[derive(Debug)]
+struct Section<'a> {
+    name: &'a str,
+    address: u32,
+    offset: usize,
+    size: usize,
+    link: usize,
+}
+
+impl<'a> Section<'a> {
+    fn data(&self, elf: &'a [u8]) -> Option<&'a [u8]> {
+        elf.get(self.offset..self.offset.checked_add(self.size)?)
+    }
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> Option<usize> {
+    Some(u16::from_le_bytes(bytes.get(offset..offset.checked_add(2)?)?.try_into().ok()?) as usize)
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> Option<usize> {
+    Some(u32::from_le_bytes(bytes.get(offset..offset.checked_add(4)?)?.try_into().ok()?) as usize)
+}
+
+/// the null terminated string at the start of `bytes`
+fn c_str(bytes: &[u8]) -> Option<&str> {
+    core::str::from_utf8(&bytes[..bytes.iter().position(|&b| b == 0)?]).ok()
+}
+
+/// the sections of a 32 bit little endian ELF file
+fn elf32_sections(elf: &[u8]) -> Option<Vec<Section<'_>>> {
+    // ELFCLASS32 & ELFDATA2LSB
+    if elf.get(..6)? != b"\x7fELF\x01\x01" {
+        return None;
+    }
+    let (section_headers, section_header_size, num_sections, names_index) = (u32_at(elf, 0x20)?, u16_at(elf, 0x2e)?, u16_at(elf, 0x30)?, u16_at(elf, 0x32)?);
+    let section_header = |index: usize| section_headers + index * section_header_size;
+    let names_offset = u32_at(elf, section_header(names_index) + 0x10)?;
+    (0..num_sections).map(|index| {
+        let header = section_header(index);
+        Some(Section {
+            name: c_str(elf.get(names_offset.checked_add(u32_at(elf, header)?)?..)?)?,
+            address: u32_at(elf, header + 0x0c)? as u32,
+            offset: u32_at(elf, header + 0x10)?,
+            size: u32_at(elf, header + 0x14)?,
+            link: u32_at(elf, header + 0x18)?,
+        })
+    }).collect()
+}
+
+/// a function or mapping symbol from the symbol table of an ELF file
+#[derive(Debug)]
+struct Symbol<'a> {
+    name: &'a str,
+    address: u32,
+    size: u32,
+    is_function: bool,
+}
+
+const STT_FUNC: u8 = 2;
+
+fn elf32_symbols<'a>(elf: &'a [u8], sections: &[Section<'a>]) -> Option<Vec<Symbol<'a>>> {
+    let symbol_table = sections.iter().find(|section| section.name == ".symtab")?;
+    let names = sections.get(symbol_table.link)?.data(elf)?;
+    symbol_table.data(elf)?.chunks_exact(16).map(|entry| {
+        Some(Symbol {
+            name: c_str(names.get(u32_at(entry, 0)?..)?)?,
+            // the lowest bit of a Thumb function's address is set
+            address: u32_at(entry, 4)? as u32 & !1,
+            size: u32_at(entry, 8)? as u32,
+            is_function: entry[12] & 0xf == STT_FUNC,
+        })
+    }).collect()
+}
+
+/// the frame size of each function in a .stack_sizes section, which is a list of addresses followed by ULEB128 sizes
+fn stack_sizes(section: &[u8]) -> Option<HashMap<u32,usize>> {
+    let mut sizes = HashMap::new();
+    let mut offset = 0;
+    while offset < section.len() {
+        let address = u32_at(section, offset)? as u32 & !1;
+        offset += 4;
+        let mut size = 0_usize;
+        for shift in (0..).step_by(7) {
+            let byte = *section.get(offset)?;
+            offset += 1;
+            size |= usize::from(byte & 0x7f).checked_shl(shift)?;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        sizes.insert(address, size);
+    }
+    Some(sizes)
+}
+
+/// what a Thumb instruction does, as far as the stack analysis is concerned
+#[derive(Debug,PartialEq)]
+enum Instruction {
+    /// a branch to an address, which is a call or tail call if it leaves the function
+    Branch(u32),
+    /// a call or jump through a register, e.g. through a function pointer
+    IndirectBranch,
+    /// MOVW, which sets a register to a 16 bit value
+    MoveBottom { register: u32, value: u32 },
+    /// MOVT, which sets the top 16 bits of a register
+    MoveTop { register: u32, value: u32 },
+    /// PUSH, VPUSH or SUB SP, which grow the stack by this many bytes
+    GrowStack(usize),
+    Other,
+}
+
+fn sign_extend(value: u32, bits: u32) -> u32 {
+    (((value << (32 - bits)) as i32) >> (32 - bits)) as u32
+}
+
+/// decode a 16 or 32 bit Thumb-2 instruction at `address` & return its length in bytes & what it does.
+/// returns (BX LR), loads into PC & jump tables (TBB/TBH) don't leave the function, so they aren't branches here
+fn decode_thumb(code: &[u8], address: u32) -> Option<(usize, Instruction)> {
+    let hw1 = u16_at(code, 0)? as u32;
+    let pc = address.wrapping_add(4);
+    if !matches!(hw1 >> 11, 0b11101..=0b11111) {
+        let instruction = if hw1 & 0xff87 == 0x4780 {
+            // BLX Rm
+            Instruction::IndirectBranch
+        } else if hw1 & 0xff87 == 0x4700 && (hw1 >> 3) & 0xf != 14 {
+            // BX Rm, other than BX LR
+            Instruction::IndirectBranch
+        } else if hw1 & 0xf800 == 0xe000 {
+            // B
+            Instruction::Branch(pc.wrapping_add(sign_extend((hw1 & 0x7ff) << 1, 12)))
+        } else if hw1 & 0xf000 == 0xd000 && (hw1 >> 9) & 0x7 != 0x7 {
+            // B<cond>, other than UDF & SVC
+            Instruction::Branch(pc.wrapping_add(sign_extend((hw1 & 0xff) << 1, 9)))
+        } else if hw1 & 0xf500 == 0xb100 {
+            // CBZ & CBNZ
+            Instruction::Branch(pc.wrapping_add(((hw1 >> 3) & 0x1f) << 1 | ((hw1 >> 9) & 1) << 6))
+        } else if hw1 & 0xfe00 == 0xb400 {
+            // PUSH
+            Instruction::GrowStack((hw1 & 0x1ff).count_ones() as usize * 4)
+        } else if hw1 & 0xff80 == 0xb080 {
+            // SUB SP, SP, #imm
+            Instruction::GrowStack((hw1 & 0x7f) as usize * 4)
+        } else {
+            Instruction::Other
+        };
+        return Some((2, instruction));
+    }
+    let hw2 = u16_at(code, 2)? as u32;
+    let instruction = if hw1 == 0xe92d {
+        // PUSH.W
+        Instruction::GrowStack(hw2.count_ones() as usize * 4)
+    } else if hw1 & 0xffbf == 0xed2d {
+        // VPUSH
+        Instruction::GrowStack((hw2 & 0xff) as usize * 4)
+    } else if hw1 & 0xfb70 == 0xf240 && hw2 & 0x8000 == 0 {
+        // MOVW & MOVT
+        let (register, value) = ((hw2 >> 8) & 0xf, (hw1 & 0xf) << 12 | ((hw1 >> 10) & 1) << 11 | ((hw2 >> 12) & 0x7) << 8 | (hw2 & 0xff));
+        if hw1 & 0x80 == 0 { Instruction::MoveBottom { register, value } } else { Instruction::MoveTop { register, value } }
+    } else if hw1 & 0xf800 == 0xf000 && hw2 & 0x8000 != 0 {
+        let (s, j1, j2) = ((hw1 >> 10) & 1, (hw2 >> 13) & 1, (hw2 >> 11) & 1);
+        match hw2 & 0xd000 {
+            // BL & B.W
+            0xd000 | 0x9000 => {
+                let (i1, i2) = (!(j1 ^ s) & 1, !(j2 ^ s) & 1);
+                Instruction::Branch(pc.wrapping_add(sign_extend(s << 24 | i1 << 23 | i2 << 22 | (hw1 & 0x3ff) << 12 | (hw2 & 0x7ff) << 1, 25)))
+            },
+            // BLX to Arm code, which Cortex-M can't run
+            0xc000 => Instruction::IndirectBranch,
+            // B<cond>.W, other than the miscellaneous control instructions
+            0x8000 if (hw1 >> 7) & 0x7 != 0x7 => {
+                Instruction::Branch(pc.wrapping_add(sign_extend(s << 20 | j2 << 19 | j1 << 18 | (hw1 & 0x3f) << 12 | (hw2 & 0x7ff) << 1, 21)))
+            },
+            _ => Instruction::Other,
+        }
+    } else {
+        Instruction::Other
+    };
+    Some((4, instruction))
+}
+
+/// a function in the call graph
+#[derive(Debug)]
+struct Function {
+    name: String,
+    /// the size of its own stack frame. functions written in assembly, such as __aeabi_uldivmod, have no frame size recorded in .stack_sizes,
+    /// so theirs is added up from their PUSH, VPUSH & SUB SP instructions
+    frame: usize,
+    /// the addresses of the functions that it calls or tail calls
+    calls: Vec<u32>,
+    /// whether it calls through a function pointer
+    calls_indirect: bool,
+}
+
+/// the functions of a Thumb binary & the calls between them
+#[derive(Debug)]
+struct CallGraph {
+    functions: BTreeMap<u32,Function>,
+    /// the functions whose address is stored in .rodata, .data or a literal pool, or loaded with MOVW & MOVT,
+    /// which a call through a function pointer is assumed to go to. the vector table is left out, as the CPU calls those functions itself
+    address_taken: Vec<u32>,
+}
+
+impl CallGraph {
+    fn read(elf_path: &Path) -> Result<Self,String> {
+        let elf = fs::read(elf_path).map_err(|e| format!("failed to read {}: {e}", elf_path.display()))?;
+        Self::parse(&elf).ok_or_else(|| format!("{} isn't a 32 bit ELF file with .text, .symtab & .stack_sizes sections", elf_path.display()))
+    }
+
+    fn parse(elf: &[u8]) -> Option<Self> {
+        let sections = elf32_sections(elf)?;
+        let text = sections.iter().find(|section| section.name == ".text")?;
+        let text_data = text.data(elf)?;
+        let frames = stack_sizes(sections.iter().find(|section| section.name == ".stack_sizes")?.data(elf)?)?;
+        let symbols = elf32_symbols(elf, &sections)?;
+        // $d marks the start of data in the middle of code, such as a literal pool, & $t marks the start of Thumb code
+        let data_ranges: BTreeMap<u32,bool> = symbols.iter()
+            .filter_map(|symbol| Some((symbol.address, match symbol.name.split('.').next()? { "$d" => true, "$t" => false, _ => return None })))
+            .collect();
+        let is_data = |address: u32| data_ranges.range(..=address).next_back().is_some_and(|(_, &is_data)| is_data);
+        // the values that could be function addresses, which have the lowest bit set
+        let mut constants: Vec<u32> = sections.iter()
+            .filter(|section| section.name == ".rodata" || section.name == ".data")
+            .filter_map(|section| section.data(elf))
+            .flat_map(|data| data.chunks_exact(4).map(|word| u32::from_le_bytes(word.try_into().unwrap())))
+            .collect();
+        let text_end = text.address + text.size as u32;
+        let mut functions = BTreeMap::new();
+        for symbol in symbols.iter().filter(|symbol| symbol.is_function && symbol.address >= text.address && symbol.address + symbol.size <= text_end) {
+            let end = symbol.address + symbol.size;
+            let (mut address, mut calls, mut calls_indirect, mut pushed) = (symbol.address, Vec::new(), false, 0);
+            let mut bottom_halves = [None; 16];
+            while address < end {
+                let code = &text_data[(address - text.address) as usize..];
+                if is_data(address) {
+                    if address % 4 == 0 {
+                        constants.push(u32_at(code, 0)? as u32);
+                    }
+                    address += 2;
+                    continue;
+                }
+                let (length, instruction) = decode_thumb(code, address)?;
+                match instruction {
+                    Instruction::Branch(target) if !(symbol.address..end).contains(&target) => calls.push(target),
+                    Instruction::IndirectBranch => calls_indirect = true,
+                    Instruction::MoveBottom { register, value } => bottom_halves[register as usize] = Some(value),
+                    Instruction::MoveTop { register, value } => constants.extend(bottom_halves[register as usize].map(|bottom| value << 16 | bottom)),
+                    Instruction::GrowStack(bytes) => pushed += bytes,
+                    _ => {},
+                }
+                address += length as u32;
+            }
+            calls.sort_unstable();
+            calls.dedup();
+            let frame = frames.get(&symbol.address).copied().unwrap_or(pushed);
+            functions.insert(symbol.address, Function { name: format!("{:#}", rustc_demangle::demangle(symbol.name)), frame, calls, calls_indirect });
+        }
+        let mut address_taken: Vec<u32> = constants.into_iter().filter(|&value| value & 1 == 1 && functions.contains_key(&(value & !1))).map(|value| value & !1).collect();
+        address_taken.sort_unstable();
+        address_taken.dedup();
+        Some(Self { functions, address_taken })
+    }
+
+    /// the worst-case stack usage of the function whose path ends with `name`, including its own frame
+    fn worst_case_stack(&self, name: &str) -> Result<usize,String> {
+        let suffix = format!("::{name}");
+        let (&address, _) = self.functions.iter().find(|(_, function)| function.name.ends_with(&suffix)).ok_or_else(|| format!("{name} isn't in the binary"))?;
+        self.stack_from(address, &mut Vec::new(), &mut HashMap::new())
+    }
+
+    fn stack_from(&self, address: u32, path: &mut Vec<u32>, known: &mut HashMap<u32,usize>) -> Result<usize,String> {
+        if let Some(&stack) = known.get(&address) {
+            return Ok(stack);
+        }
+        let function = self.functions.get(&address).ok_or_else(|| format!("a call to {address:#010x} doesn't go to the start of a function"))?;
+        if path.contains(&address) {
+            return Err(format!("{} is recursive, so its stack usage is unbounded", function.name));
+        }
+        let indirect_calls = if function.calls_indirect { self.address_taken.as_slice() } else { &[] };
+        path.push(address);
+        let mut deepest_call = 0;
+        for &callee in function.calls.iter().chain(indirect_calls) {
+            deepest_call = deepest_call.max(self.stack_from(callee, path, known)?);
+        }
+        path.pop();
+        known.insert(address, function.frame + deepest_call);
+        Ok(function.frame + deepest_call)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_elf32_section_size() {
+        // an ELF header followed by 3 section headers: null, .text & the section name table
+        let mut elf = vec![0_u8; 0x34 + 3 * 0x28];
+        elf[..6].copy_from_slice(b"\x7fELF\x01\x01");
+        elf[0x20..0x24].copy_from_slice(&0x34_u32.to_le_bytes());
+        elf[0x2e..0x30].copy_from_slice(&0x28_u16.to_le_bytes());
+        elf[0x30..0x32].copy_from_slice(&3_u16.to_le_bytes());
+        elf[0x32..0x34].copy_from_slice(&2_u16.to_le_bytes());
+        let names = b"\0.text\0.shstrtab\0";
+        let names_offset = elf.len();
+        elf.extend_from_slice(names);
+        let text_header = 0x34 + 0x28;
+        elf[text_header..text_header + 4].copy_from_slice(&1_u32.to_le_bytes());
+        elf[text_header + 0x14..text_header + 0x18].copy_from_slice(&1234_u32.to_le_bytes());
+        let names_header = 0x34 + 2 * 0x28;
+        elf[names_header..names_header + 4].copy_from_slice(&7_u32.to_le_bytes());
+        elf[names_header + 0x10..names_header + 0x14].copy_from_slice(&(names_offset as u32).to_le_bytes());
+        assert_eq!(Some(1234), elf32_section_size(&elf, ".text"));
+        assert_eq!(None, elf32_section_size(&elf, ".data"));
+    }
+
+    #[test]
+    fn test_stack_sizes() {
+        let section = [0x59, 0x04, 0x00, 0x08, 0x10, 0x00, 0x10, 0x00, 0x08, 0xf0, 0x04];
+        assert_eq!(Some(HashMap::from([(0x0800_0458, 16), (0x0800_1000, 624)])), stack_sizes(&section));
+        assert_eq!(None, stack_sizes(&section[..10]));
+    }
+
+    #[test]
+    fn test_decode_thumb() {
+        // bl 0x08000458 from 0x08003040
+        assert_eq!(Some((4, Instruction::Branch(0x0800_0458))), decode_thumb(&[0xfd, 0xf7, 0x0a, 0xfa], 0x0800_3040));
+        // b.w 0x08003040 from 0x08000458
+        assert_eq!(Some((4, Instruction::Branch(0x0800_3040))), decode_thumb(&[0x02, 0xf0, 0xf2, 0xbd], 0x0800_0458));
+        // bne.w 0x08000500 from 0x08000400
+        assert_eq!(Some((4, Instruction::Branch(0x0800_0500))), decode_thumb(&[0x40, 0xf0, 0x7e, 0x80], 0x0800_0400));
+        // b 0x080003fc & beq 0x08000410 from 0x08000400
+        assert_eq!(Some((2, Instruction::Branch(0x0800_03fc))), decode_thumb(&[0xfc, 0xe7], 0x0800_0400));
+        assert_eq!(Some((2, Instruction::Branch(0x0800_0410))), decode_thumb(&[0x06, 0xd0], 0x0800_0400));
+        // cbz r0, 0x08000442 from 0x08000400
+        assert_eq!(Some((2, Instruction::Branch(0x0800_0442))), decode_thumb(&[0xf8, 0xb1], 0x0800_0400));
+        // blx r3, bx r3 & bx lr
+        assert_eq!(Some((2, Instruction::IndirectBranch)), decode_thumb(&[0x98, 0x47], 0x0800_0400));
+        assert_eq!(Some((2, Instruction::IndirectBranch)), decode_thumb(&[0x18, 0x47], 0x0800_0400));
+        assert_eq!(Some((2, Instruction::Other)), decode_thumb(&[0x70, 0x47], 0x0800_0400));
+        // movw r1, #0x5a35, movt r1, #0x0800 & movw r10, #0xffff
+        assert_eq!(Some((4, Instruction::MoveBottom { register: 1, value: 0x5a35 })), decode_thumb(&[0x45, 0xf6, 0x35, 0x21], 0x0800_0400));
+        assert_eq!(Some((4, Instruction::MoveTop { register: 1, value: 0x0800 })), decode_thumb(&[0xc0, 0xf6, 0x00, 0x01], 0x0800_0400));
+        assert_eq!(Some((4, Instruction::MoveBottom { register: 10, value: 0xffff })), decode_thumb(&[0x4f, 0xf6, 0xff, 0x7a], 0x0800_0400));
+        // push {r4, r5, r7, lr}, push.w {r4-r11, lr}, vpush {d8, d9} & sub sp, #16
+        assert_eq!(Some((2, Instruction::GrowStack(16))), decode_thumb(&[0xb0, 0xb5], 0x0800_0400));
+        assert_eq!(Some((4, Instruction::GrowStack(36))), decode_thumb(&[0x2d, 0xe9, 0xf0, 0x4f], 0x0800_0400));
+        assert_eq!(Some((4, Instruction::GrowStack(16))), decode_thumb(&[0x2d, 0xed, 0x04, 0x8b], 0x0800_0400));
+        assert_eq!(Some((2, Instruction::GrowStack(16))), decode_thumb(&[0x84, 0xb0], 0x0800_0400));
+        // ldr.w r0, [r1, #4] & udf #0
+        assert_eq!(Some((4, Instruction::Other)), decode_thumb(&[0xd1, 0xf8, 0x04, 0x00], 0x0800_0400));
+        assert_eq!(Some((2, Instruction::Other)), decode_thumb(&[0x00, 0xde], 0x0800_0400));
+        assert_eq!(None, decode_thumb(&[0xfd, 0xf7], 0x0800_0400));
+    }
+
+    fn function(name: &str, frame: usize, calls: &[u32]) -> Function {
+        Function { name: name.into(), frame, calls: calls.into(), calls_indirect: false }
+    }
+
+    #[test]
+    fn test_worst_case_stack() {
+        let mut call_graph = CallGraph { functions: BTreeMap::from([
+            (0x100, function("footprint::sample_serialize", 16, &[0x200, 0x300])),
+            (0x200, function("lil_jwt::serialize_jwt", 200, &[0x400])),
+            (0x300, function("memcpy", 8, &[])),
+            (0x400, function("sha2::compress", 300, &[0x300])),
+            (0x500, function("<footprint::Clock as lil_jwt::Clock>::now", 600, &[])),
+        ]), address_taken: Vec::new() };
+        assert_eq!(Ok(524), call_graph.worst_case_stack("sample_serialize"));
+        assert_eq!(Ok(508), call_graph.worst_case_stack("serialize_jwt"));
+        assert!(call_graph.worst_case_stack("sample_deserialize").is_err());
+        // a call through a function pointer goes to any function whose address is taken
+        call_graph.functions.get_mut(&0x400).unwrap().calls_indirect = true;
+        assert_eq!(Ok(524), call_graph.worst_case_stack("sample_serialize"));
+        call_graph.address_taken.push(0x500);
+        assert_eq!(Ok(1116), call_graph.worst_case_stack("sample_serialize"));
+        call_graph.address_taken.push(0x200);
+        assert!(call_graph.worst_case_stack("sample_serialize").unwrap_err().contains("recursive"));
+        call_graph.address_taken.clear();
+        call_graph.functions.get_mut(&0x300).unwrap().calls.push(0x200);
+        assert!(call_graph.worst_case_stack("sample_serialize").unwrap_err().contains("lil_jwt::serialize_jwt is recursive"));
+        call_graph.functions.get_mut(&0x300).unwrap().calls = vec![0x600];
+        assert!(call_graph.worst_case_stack("compress").unwrap_err().contains("0x00000600"));
+    }
+}